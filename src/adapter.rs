@@ -0,0 +1,159 @@
+use crate::{Candle, Next, TaUtilsResult};
+
+/// Conversion of iterator and stream items into the input of an indicator.
+///
+/// Besides the identity, references to any sized [`Candle`] convert to
+/// `&dyn Candle`, so `bars.iter()` can feed a `Next<&dyn Candle>` directly.
+pub trait IntoInput<T> {
+    fn into_input(self) -> T;
+}
+
+impl<T> IntoInput<T> for T {
+    fn into_input(self) -> T {
+        self
+    }
+}
+
+impl<'a, C: Candle> IntoInput<&'a dyn Candle> for &'a C {
+    fn into_input(self) -> &'a dyn Candle {
+        self
+    }
+}
+
+/// Iterator adapter feeding every item to an indicator, see [`NextExt::indicator`].
+#[derive(Debug, Clone)]
+pub struct Indicator<I, N, T> {
+    iter: I,
+    indicator: N,
+    _input: std::marker::PhantomData<fn(T)>,
+}
+
+impl<I, N, T> Indicator<I, N, T> {
+    pub fn indicator(&self) -> &N {
+        &self.indicator
+    }
+
+    pub fn into_inner(self) -> (I, N) {
+        (self.iter, self.indicator)
+    }
+}
+
+impl<I, N, T> Iterator for Indicator<I, N, T>
+where
+    I: Iterator,
+    I::Item: IntoInput<T>,
+    N: Next<T>,
+{
+    type Item = TaUtilsResult<N::Output>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.iter.next()?;
+        Some(self.indicator.next(input.into_input()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I, N, T> ExactSizeIterator for Indicator<I, N, T>
+where
+    I: ExactSizeIterator,
+    I::Item: IntoInput<T>,
+    N: Next<T>,
+{
+}
+
+/// Extension trait plugging indicators into iterators.
+pub trait NextExt: Iterator + Sized {
+    /// Feeds every item to `indicator` and yields its outputs, pass `&mut indicator`
+    /// to keep using it afterwards.
+    ///
+    /// The iterator keeps going after an error, collect into a `TaUtilsResult<Vec<_>>`
+    /// to stop at the first one.
+    fn indicator<N, T>(self, indicator: N) -> Indicator<Self, N, T>
+    where
+        Self::Item: IntoInput<T>,
+        N: Next<T>,
+    {
+        Indicator {
+            iter: self,
+            indicator,
+            _input: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<I: Iterator> NextExt for I {}
+
+#[cfg(feature = "stream")]
+pub use stream::{IndicatorStream, NextStreamExt};
+
+#[cfg(feature = "stream")]
+mod stream {
+    use std::marker::PhantomData;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures_core::Stream;
+
+    use super::IntoInput;
+    use crate::{Next, TaUtilsResult};
+
+    /// Stream adapter feeding every item to an indicator, see [`NextStreamExt::indicator`].
+    #[derive(Debug, Clone)]
+    pub struct IndicatorStream<S, N, T> {
+        stream: S,
+        indicator: N,
+        _input: PhantomData<fn(T)>,
+    }
+
+    impl<S, N, T> IndicatorStream<S, N, T> {
+        pub fn indicator(&self) -> &N {
+            &self.indicator
+        }
+
+        pub fn into_inner(self) -> (S, N) {
+            (self.stream, self.indicator)
+        }
+    }
+
+    impl<S, N, T> Stream for IndicatorStream<S, N, T>
+    where
+        S: Stream + Unpin,
+        S::Item: IntoInput<T>,
+        N: Next<T> + Unpin,
+    {
+        type Item = TaUtilsResult<N::Output>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            let this = self.get_mut();
+            Pin::new(&mut this.stream)
+                .poll_next(cx)
+                .map(|input| input.map(|input| this.indicator.next(input.into_input())))
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            self.stream.size_hint()
+        }
+    }
+
+    /// Extension trait plugging indicators into async streams.
+    pub trait NextStreamExt: Stream + Sized {
+        /// Feeds every item to `indicator` and yields its outputs, the stream must be
+        /// `Unpin` (wrap it with `Box::pin` otherwise).
+        fn indicator<N, T>(self, indicator: N) -> IndicatorStream<Self, N, T>
+        where
+            Self::Item: IntoInput<T>,
+            N: Next<T>,
+        {
+            IndicatorStream {
+                stream: self,
+                indicator,
+                _input: PhantomData,
+            }
+        }
+    }
+
+    impl<S: Stream> NextStreamExt for S {}
+}
@@ -20,6 +20,31 @@ pub enum TaUtilsError {
 
     #[error("Cmp error, {0}")]
     Cmp(#[from] OutputError),
+
+    #[error("Invalid bar, {0}")]
+    Bar(#[from] BarError),
+
+    #[error("IO error, {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Malformed input data, `line` is 1 based and `offset` is the byte offset of the start of the line.
+    #[error("Parse error at line {line} (byte {offset}): {message}")]
+    Parse {
+        line: u64,
+        offset: u64,
+        message: String,
+    },
+
+    /// Error raised by the input at `index` of a batch.
+    #[error("Error at input {index}, {source}")]
+    Batch {
+        index: usize,
+        source: Box<TaUtilsError>,
+    },
+
+    /// Corrupted or unsupported binary data, `offset` is the byte offset where the problem was found.
+    #[error("Format error at byte {offset}: {message}")]
+    Format { offset: u64, message: String },
 }
 
 #[derive(thiserror::Error, Debug, PartialEq)]
@@ -30,6 +55,27 @@ pub enum OutputError {
     LengthMismatch(usize, usize),
     #[error("Invalid output shape {0}")]
     InvalidOutputShape(OutputShape),
+    /// The value is not available, see `OutputType::Missing`.
+    #[error("Missing value")]
+    Missing,
+}
+
+/// Broken OHLCV invariant found by `Bar::validate`.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum BarError {
+    #[error("field '{field}' is not finite ({value})")]
+    NonFinite { field: &'static str, value: f64 },
+    #[error("high {high} is lower than low {low}")]
+    HighBelowLow { high: f64, low: f64 },
+    #[error("field '{field}' ({value}) is outside the low-high range [{low}, {high}]")]
+    OutOfRange {
+        field: &'static str,
+        value: f64,
+        low: f64,
+        high: f64,
+    },
+    #[error("volume {0} is negative")]
+    NegativeVolume(f64),
 }
 
 impl PartialEq for TaUtilsError {
@@ -48,9 +94,53 @@ impl PartialEq for TaUtilsError {
                     actual: a2,
                 },
             ) => e1 == e2 && a1 == a2,
+            (TaUtilsError::Cmp(a), TaUtilsError::Cmp(b)) => a == b,
+            (TaUtilsError::Bar(a), TaUtilsError::Bar(b)) => a == b,
+            (
+                TaUtilsError::Parse {
+                    line: l1,
+                    offset: o1,
+                    message: m1,
+                },
+                TaUtilsError::Parse {
+                    line: l2,
+                    offset: o2,
+                    message: m2,
+                },
+            ) => l1 == l2 && o1 == o2 && m1 == m2,
+            (
+                TaUtilsError::Batch {
+                    index: i1,
+                    source: s1,
+                },
+                TaUtilsError::Batch {
+                    index: i2,
+                    source: s2,
+                },
+            ) => i1 == i2 && s1 == s2,
+            (
+                TaUtilsError::Format {
+                    offset: o1,
+                    message: m1,
+                },
+                TaUtilsError::Format {
+                    offset: o2,
+                    message: m2,
+                },
+            ) => o1 == o2 && m1 == m2,
             _ => false,
         }
     }
 }
 
+impl TaUtilsError {
+    /// Wraps the error as raised by the input at `index` of a batch.
+    pub fn at_index(self, index: usize) -> Self {
+        TaUtilsError::Batch {
+            index,
+            source: Box::new(self),
+        }
+    }
+}
+
 pub type TaUtilsResult<T> = Result<T, TaUtilsError>;
@@ -0,0 +1,10 @@
+//! Readers and writers for market data streams, each format behind its own cargo feature.
+
+#[cfg(feature = "csv")]
+pub mod csv;
+
+#[cfg(feature = "ndjson")]
+pub mod ndjson;
+
+#[cfg(feature = "columnar")]
+pub mod columnar;
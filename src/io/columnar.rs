@@ -0,0 +1,762 @@
+//! Compact binary format for bar history.
+//!
+//! A file starts with a 16 bytes header: the `CTAB` magic, the format version
+//! (`u16`), flags (`u16`, bit 0 for timestamps and bit 1 for checksums) and the
+//! timeframe of the bars in seconds (`u64`, 0 if unknown). It is followed by
+//! blocks, each made of its row count (`u32`) and 4 reserved bytes, the optional
+//! timestamps column (`i64`, `i64::MIN` if unknown), the open, high, low, close,
+//! price and volume columns (`f64`) and the optional FNV-1a checksum (`u64`) of
+//! the block. Every value is little endian and 8 bytes aligned.
+//!
+//! Blocks let [`ColumnarWriter`] append bars incrementally while keeping the
+//! data column major, and [`ColumnarReader`] reads any byte slice, such as a
+//! memory mapped file, without copying it.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Candle, MarketData, TaUtilsError, TaUtilsResult, Timeframe};
+
+const MAGIC: [u8; 4] = *b"CTAB";
+const HEADER_LEN: usize = 16;
+const BLOCK_HEADER_LEN: usize = 8;
+const FLAG_TIMESTAMPS: u16 = 1;
+const FLAG_CHECKSUM: u16 = 1 << 1;
+const MISSING_TIMESTAMP: i64 = i64::MIN;
+
+/// Latest version of the format, the one written by [`ColumnarWriter`].
+pub const VERSION: u16 = 1;
+
+/// The `f64` columns of a file, in storage order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Field {
+    Open,
+    High,
+    Low,
+    Close,
+    Price,
+    Volume,
+}
+
+impl Field {
+    pub const ALL: [Field; 6] = [
+        Field::Open,
+        Field::High,
+        Field::Low,
+        Field::Close,
+        Field::Price,
+        Field::Volume,
+    ];
+
+    fn value(&self, candle: &dyn Candle) -> f64 {
+        match self {
+            Field::Open => candle.open(),
+            Field::High => candle.high(),
+            Field::Low => candle.low(),
+            Field::Close => candle.close(),
+            Field::Price => candle.price(),
+            Field::Volume => candle.volume(),
+        }
+    }
+}
+
+/// File level settings, stored in the header of every file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnarOptions {
+    /// Whether the timestamp of each bar is stored.
+    pub timestamps: bool,
+    /// Whether each block is followed by a checksum, verified when reading.
+    pub checksum: bool,
+    /// Timeframe of the bars, reported by every [`BarView`].
+    pub timeframe: Option<Timeframe>,
+    /// Number of bars buffered by [`ColumnarWriter`] before writing a block, not stored.
+    pub block_size: usize,
+}
+
+impl Default for ColumnarOptions {
+    fn default() -> Self {
+        Self {
+            timestamps: true,
+            checksum: true,
+            timeframe: None,
+            block_size: 4096,
+        }
+    }
+}
+
+impl ColumnarOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_timestamps(mut self, val: bool) -> Self {
+        self.timestamps = val;
+        self
+    }
+
+    pub fn set_checksum(mut self, val: bool) -> Self {
+        self.checksum = val;
+        self
+    }
+
+    pub fn set_timeframe(mut self, val: Timeframe) -> Self {
+        self.timeframe = Some(val);
+        self
+    }
+
+    pub fn set_block_size(mut self, val: usize) -> Self {
+        self.block_size = val;
+        self
+    }
+
+    fn flags(&self) -> u16 {
+        let mut flags = 0;
+        if self.timestamps {
+            flags |= FLAG_TIMESTAMPS;
+        }
+        if self.checksum {
+            flags |= FLAG_CHECKSUM;
+        }
+        flags
+    }
+
+    fn columns(&self) -> usize {
+        Field::ALL.len() + usize::from(self.timestamps)
+    }
+
+    fn encode_header(&self) -> [u8; HEADER_LEN] {
+        let mut header = [0; HEADER_LEN];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..6].copy_from_slice(&VERSION.to_le_bytes());
+        header[6..8].copy_from_slice(&self.flags().to_le_bytes());
+        let timeframe = self.timeframe.map_or(0, |timeframe| timeframe.as_secs());
+        header[8..].copy_from_slice(&timeframe.to_le_bytes());
+        header
+    }
+
+    fn decode_header(data: &[u8]) -> TaUtilsResult<Self> {
+        if data.len() < HEADER_LEN {
+            return Err(format_error(0, "file is shorter than the header"));
+        }
+        if data[..4] != MAGIC {
+            return Err(format_error(0, "not a columnar bar file"));
+        }
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        if version == 0 || version > VERSION {
+            return Err(format_error(
+                4,
+                format!("unsupported version {version}, latest is {VERSION}"),
+            ));
+        }
+        let flags = u16::from_le_bytes([data[6], data[7]]);
+        if flags & !(FLAG_TIMESTAMPS | FLAG_CHECKSUM) != 0 {
+            return Err(format_error(6, format!("unknown flags {flags:#06x}")));
+        }
+        let timeframe = match read_u64(data, 8) {
+            0 => None,
+            seconds => Some(
+                Timeframe::seconds(seconds)
+                    .map_err(|_| format_error(8, format!("invalid timeframe {seconds}s")))?,
+            ),
+        };
+        Ok(Self {
+            timestamps: flags & FLAG_TIMESTAMPS != 0,
+            checksum: flags & FLAG_CHECKSUM != 0,
+            timeframe,
+            block_size: Self::default().block_size,
+        })
+    }
+
+    /// Returns the number of rows and the total length of the block starting at
+    /// `offset`, given its first bytes and the number of bytes left in the file.
+    fn block_len(
+        &self,
+        header: &[u8],
+        available: usize,
+        offset: usize,
+    ) -> TaUtilsResult<(usize, usize)> {
+        if available < BLOCK_HEADER_LEN {
+            return Err(format_error(offset, "truncated block header"));
+        }
+        let rows = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let len = BLOCK_HEADER_LEN + rows * self.columns() * 8 + if self.checksum { 8 } else { 0 };
+        if available < len {
+            return Err(format_error(
+                offset,
+                format!("truncated block of {rows} rows, expected {len} bytes, found {available}"),
+            ));
+        }
+        Ok((rows, len))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    /// Index of the first row of the block in the file.
+    start: usize,
+    rows: usize,
+    /// Byte offset of the first column.
+    offset: usize,
+}
+
+/// Zero copy reader of a columnar bar file held in memory.
+///
+/// Opening validates the header and the layout of every block, and verifies
+/// the checksums if the file has them. Bars are then read straight from the
+/// underlying bytes, either row by row as [`BarView`]s or column by column.
+#[derive(Clone)]
+pub struct ColumnarReader<'a> {
+    data: &'a [u8],
+    options: ColumnarOptions,
+    blocks: Vec<Block>,
+    len: usize,
+}
+
+impl<'a> ColumnarReader<'a> {
+    pub fn new(data: &'a [u8]) -> TaUtilsResult<Self> {
+        let options = ColumnarOptions::decode_header(data)?;
+        let mut blocks = Vec::new();
+        let mut offset = HEADER_LEN;
+        let mut len = 0;
+        while offset < data.len() {
+            let (rows, block_len) =
+                options.block_len(&data[offset..], data.len() - offset, offset)?;
+            if options.checksum {
+                let end = offset + block_len - 8;
+                if fnv1a(&data[offset..end]) != read_u64(data, end) {
+                    return Err(format_error(offset, "checksum mismatch"));
+                }
+            }
+            blocks.push(Block {
+                start: len,
+                rows,
+                offset: offset + BLOCK_HEADER_LEN,
+            });
+            len += rows;
+            offset += block_len;
+        }
+        Ok(Self {
+            data,
+            options,
+            blocks,
+            len,
+        })
+    }
+
+    /// The settings stored in the file header, `block_size` is left to its default.
+    pub fn options(&self) -> &ColumnarOptions {
+        &self.options
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<BarView<'a>> {
+        if index >= self.len {
+            return None;
+        }
+        let block = self.blocks[self.blocks.partition_point(|block| block.start <= index) - 1];
+        Some(self.view(block, index - block.start))
+    }
+
+    pub fn iter(&self) -> BarViews<'_, 'a> {
+        BarViews {
+            reader: self,
+            block: 0,
+            row: 0,
+            remaining: self.len,
+        }
+    }
+
+    /// Iterates over the values of a single column, oldest first.
+    pub fn column(&self, field: Field) -> impl Iterator<Item = f64> + '_ {
+        let column = field as usize + usize::from(self.options.timestamps);
+        self.blocks.iter().flat_map(move |block| {
+            let start = block.offset + column * block.rows * 8;
+            (0..block.rows).map(move |row| read_f64(self.data, start + row * 8))
+        })
+    }
+
+    /// Iterates over the timestamps, `None` if the file does not store them.
+    pub fn timestamps(&self) -> Option<impl Iterator<Item = Option<i64>> + '_> {
+        self.options.timestamps.then(|| {
+            self.blocks.iter().flat_map(move |block| {
+                (0..block.rows).map(move |row| read_timestamp(self.data, block.offset + row * 8))
+            })
+        })
+    }
+
+    fn view(&self, block: Block, row: usize) -> BarView<'a> {
+        BarView {
+            data: self.data,
+            offset: block.offset + row * 8,
+            stride: block.rows * 8,
+            timestamps: self.options.timestamps,
+            timeframe: self.options.timeframe,
+        }
+    }
+}
+
+impl fmt::Debug for ColumnarReader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColumnarReader")
+            .field("options", &self.options)
+            .field("blocks", &self.blocks.len())
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<'r, 'a> IntoIterator for &'r ColumnarReader<'a> {
+    type Item = BarView<'a>;
+    type IntoIter = BarViews<'r, 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the rows of a [`ColumnarReader`].
+#[derive(Debug, Clone)]
+pub struct BarViews<'r, 'a> {
+    reader: &'r ColumnarReader<'a>,
+    block: usize,
+    row: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for BarViews<'_, 'a> {
+    type Item = BarView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&block) = self.reader.blocks.get(self.block) {
+            if self.row < block.rows {
+                self.row += 1;
+                self.remaining -= 1;
+                return Some(self.reader.view(block, self.row - 1));
+            }
+            self.block += 1;
+            self.row = 0;
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for BarViews<'_, '_> {}
+
+/// A bar read in place from a columnar file, implements [`Candle`] without allocating.
+#[derive(Clone, Copy)]
+pub struct BarView<'a> {
+    data: &'a [u8],
+    /// Byte offset of the row in the first column of its block.
+    offset: usize,
+    /// Length in bytes of a column of the block.
+    stride: usize,
+    timestamps: bool,
+    timeframe: Option<Timeframe>,
+}
+
+impl BarView<'_> {
+    pub fn get(&self, field: Field) -> f64 {
+        let column = field as usize + usize::from(self.timestamps);
+        read_f64(self.data, self.offset + column * self.stride)
+    }
+}
+
+impl Candle for BarView<'_> {
+    fn open(&self) -> f64 {
+        self.get(Field::Open)
+    }
+
+    fn high(&self) -> f64 {
+        self.get(Field::High)
+    }
+
+    fn low(&self) -> f64 {
+        self.get(Field::Low)
+    }
+
+    fn close(&self) -> f64 {
+        self.get(Field::Close)
+    }
+
+    fn price(&self) -> f64 {
+        self.get(Field::Price)
+    }
+
+    fn volume(&self) -> f64 {
+        self.get(Field::Volume)
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        if self.timestamps {
+            read_timestamp(self.data, self.offset)
+        } else {
+            None
+        }
+    }
+
+    fn timeframe(&self) -> Option<Timeframe> {
+        self.timeframe
+    }
+}
+
+impl fmt::Debug for BarView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BarView")
+            .field("open", &self.open())
+            .field("high", &self.high())
+            .field("low", &self.low())
+            .field("close", &self.close())
+            .field("price", &self.price())
+            .field("volume", &self.volume())
+            .field("timestamp", &self.timestamp())
+            .field("timeframe", &self.timeframe)
+            .finish()
+    }
+}
+
+/// Writes candles to a columnar file, one block every `block_size` candles.
+///
+/// Candles are buffered until a block is complete, call [`ColumnarWriter::flush`]
+/// or [`ColumnarWriter::into_inner`] to write the pending ones.
+pub struct ColumnarWriter<W: Write> {
+    writer: W,
+    options: ColumnarOptions,
+    timestamps: Vec<i64>,
+    columns: [Vec<f64>; 6],
+    written: usize,
+    block: Vec<u8>,
+}
+
+impl ColumnarWriter<File> {
+    pub fn from_path(path: impl AsRef<Path>, options: ColumnarOptions) -> TaUtilsResult<Self> {
+        Self::new(File::create(path)?, options)
+    }
+
+    /// Opens an existing file to append candles to it, see [`ColumnarWriter::append`].
+    pub fn append_to_path(path: impl AsRef<Path>, block_size: usize) -> TaUtilsResult<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Self::append(file, block_size)
+    }
+}
+
+impl<W: Read + Write + Seek> ColumnarWriter<W> {
+    /// Continues a file written earlier, keeping the settings of its header.
+    ///
+    /// The layout of the existing blocks is checked (not their checksums) and new
+    /// blocks are written after the last one.
+    pub fn append(mut writer: W, block_size: usize) -> TaUtilsResult<Self> {
+        let end = writer.seek(SeekFrom::End(0))? as usize;
+        writer.seek(SeekFrom::Start(0))?;
+        let mut header = [0; HEADER_LEN];
+        writer.read_exact(&mut header[..end.min(HEADER_LEN)])?;
+        let options = ColumnarOptions::decode_header(&header[..end.min(HEADER_LEN)])?
+            .set_block_size(block_size);
+        let mut offset = HEADER_LEN;
+        let mut written = 0;
+        while offset < end {
+            let mut block = [0; BLOCK_HEADER_LEN];
+            let available = end - offset;
+            writer.read_exact(&mut block[..available.min(BLOCK_HEADER_LEN)])?;
+            let (rows, block_len) = options.block_len(&block, available, offset)?;
+            written += rows;
+            offset += block_len;
+            writer.seek(SeekFrom::Start(offset as u64))?;
+        }
+        Self::with_written(writer, options, written)
+    }
+}
+
+impl<W: Write> ColumnarWriter<W> {
+    /// Creates a writer and writes the file header, nothing is written if the options are invalid.
+    pub fn new(writer: W, options: ColumnarOptions) -> TaUtilsResult<Self> {
+        let mut this = Self::with_written(writer, options, 0)?;
+        this.writer.write_all(&this.options.encode_header())?;
+        Ok(this)
+    }
+
+    fn with_written(writer: W, options: ColumnarOptions, written: usize) -> TaUtilsResult<Self> {
+        check_block_size(options.block_size)?;
+        Ok(Self {
+            writer,
+            timestamps: Vec::new(),
+            columns: Default::default(),
+            written,
+            block: Vec::new(),
+            options,
+        })
+    }
+
+    pub fn options(&self) -> &ColumnarOptions {
+        &self.options
+    }
+
+    /// Number of candles in the file, including the ones not written yet.
+    pub fn len(&self) -> usize {
+        self.written + self.pending()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn write(&mut self, candle: &dyn Candle) -> TaUtilsResult<()> {
+        if self.options.timestamps {
+            self.timestamps
+                .push(candle.timestamp().unwrap_or(MISSING_TIMESTAMP));
+        }
+        for (column, field) in self.columns.iter_mut().zip(Field::ALL) {
+            column.push(field.value(candle));
+        }
+        if self.pending() >= self.options.block_size {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    pub fn write_all<'a>(
+        &mut self,
+        candles: impl IntoIterator<Item = &'a MarketData>,
+    ) -> TaUtilsResult<()> {
+        for candle in candles {
+            self.write(candle)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the pending candles as a block, even if it is not full, and flushes the writer.
+    pub fn flush(&mut self) -> TaUtilsResult<()> {
+        self.write_block()?;
+        Ok(self.writer.flush()?)
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> TaUtilsResult<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    fn pending(&self) -> usize {
+        self.columns[0].len()
+    }
+
+    fn write_block(&mut self) -> TaUtilsResult<()> {
+        let rows = self.pending();
+        if rows == 0 {
+            return Ok(());
+        }
+        self.block.clear();
+        self.block.extend_from_slice(&(rows as u32).to_le_bytes());
+        self.block.extend_from_slice(&[0; 4]);
+        for timestamp in self.timestamps.drain(..) {
+            self.block.extend_from_slice(&timestamp.to_le_bytes());
+        }
+        for column in &mut self.columns {
+            for value in column.drain(..) {
+                self.block.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        if self.options.checksum {
+            let checksum = fnv1a(&self.block);
+            self.block.extend_from_slice(&checksum.to_le_bytes());
+        }
+        self.writer.write_all(&self.block)?;
+        self.written += rows;
+        Ok(())
+    }
+}
+
+fn check_block_size(block_size: usize) -> TaUtilsResult<()> {
+    if block_size == 0 || block_size > u32::MAX as usize {
+        return Err(TaUtilsError::InvalidParameter(format!(
+            "Block size must be between 1 and {}, got {block_size}",
+            u32::MAX
+        )));
+    }
+    Ok(())
+}
+
+fn format_error(offset: usize, message: impl Into<String>) -> TaUtilsError {
+    TaUtilsError::Format {
+        offset: offset as u64,
+        message: message.into(),
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_f64(data: &[u8], offset: usize) -> f64 {
+    f64::from_bits(read_u64(data, offset))
+}
+
+fn read_timestamp(data: &[u8], offset: usize) -> Option<i64> {
+    let timestamp = read_u64(data, offset) as i64;
+    (timestamp != MISSING_TIMESTAMP).then_some(timestamp)
+}
+
+/// 64 bits FNV-1a hash, used as block checksum.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::Bar;
+
+    fn bars(count: usize) -> Vec<MarketData> {
+        (0..count)
+            .map(|i| {
+                let base = i as f64;
+                let mut bar = Bar::new()
+                    .set_open(base)
+                    .set_high(base + 2.0)
+                    .set_low(base - 1.0)
+                    .set_close(base + 1.0)
+                    .set_price(base + 0.5)
+                    .set_volume(if i % 3 == 0 { f64::NAN } else { base * 10.0 });
+                if i % 4 != 1 {
+                    bar = bar.set_open_time(i as i64 * 60_000);
+                }
+                MarketData::Bar(bar)
+            })
+            .collect()
+    }
+
+    fn write(options: ColumnarOptions, bars: &[MarketData]) -> Vec<u8> {
+        let mut writer = ColumnarWriter::new(Vec::new(), options).unwrap();
+        writer.write_all(bars).unwrap();
+        writer.into_inner().unwrap()
+    }
+
+    fn assert_bars(reader: &ColumnarReader<'_>, bars: &[MarketData]) {
+        assert_eq!(reader.len(), bars.len());
+        for (view, bar) in reader.iter().zip(bars) {
+            for field in Field::ALL {
+                assert_eq!(view.get(field).to_bits(), field.value(bar).to_bits());
+            }
+            if reader.options().timestamps {
+                assert_eq!(view.timestamp(), bar.timestamp());
+            } else {
+                assert_eq!(view.timestamp(), None);
+            }
+            assert_eq!(view.timeframe(), reader.options().timeframe);
+        }
+    }
+
+    #[test]
+    fn round_trips_across_blocks() {
+        let bars = bars(10);
+        for timestamps in [true, false] {
+            for checksum in [true, false] {
+                let options = ColumnarOptions::new()
+                    .set_timestamps(timestamps)
+                    .set_checksum(checksum)
+                    .set_timeframe(Timeframe::minutes(1).unwrap())
+                    .set_block_size(3);
+                let data = write(options.clone(), &bars);
+                let reader = ColumnarReader::new(&data).unwrap();
+                assert_eq!(reader.blocks.len(), 4);
+                assert_eq!(reader.options(), &options.set_block_size(4096));
+                assert_bars(&reader, &bars);
+                assert_eq!(reader.get(7).unwrap().open(), 7.0);
+                assert!(reader.get(10).is_none());
+                assert_eq!(reader.column(Field::Close).nth(4), Some(5.0));
+                assert_eq!(reader.timestamps().is_some(), timestamps);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_block_sizes() {
+        let mut data = Vec::new();
+        let options = ColumnarOptions::new().set_block_size(0);
+        assert!(matches!(
+            ColumnarWriter::new(&mut data, options),
+            Err(TaUtilsError::InvalidParameter(_))
+        ));
+        assert!(data.is_empty());
+
+        let data = write(ColumnarOptions::new(), &bars(2));
+        assert!(ColumnarWriter::append(Cursor::new(data), 0).is_err());
+    }
+
+    #[test]
+    fn checks_the_header() {
+        let data = write(ColumnarOptions::new().set_checksum(false), &bars(1));
+        let error_offset = |data: &[u8]| match ColumnarReader::new(data) {
+            Err(TaUtilsError::Format { offset, .. }) => offset,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(error_offset(&data[..HEADER_LEN - 1]), 0);
+
+        let mut invalid = data.clone();
+        invalid[0] = b'X';
+        assert_eq!(error_offset(&invalid), 0);
+        let mut invalid = data.clone();
+        invalid[4..6].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert_eq!(error_offset(&invalid), 4);
+        let mut invalid = data.clone();
+        invalid[6..8].copy_from_slice(&4_u16.to_le_bytes());
+        assert_eq!(error_offset(&invalid), 6);
+        let mut invalid = data.clone();
+        invalid[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(error_offset(&invalid), 8);
+
+        assert!(ColumnarReader::new(&data[..HEADER_LEN]).unwrap().is_empty());
+        assert_eq!(
+            ColumnarReader::new(&data).unwrap().options().timeframe,
+            None
+        );
+    }
+
+    #[test]
+    fn checks_the_blocks() {
+        let data = write(ColumnarOptions::new(), &bars(2));
+        let truncated = &data[..data.len() - 1];
+        assert!(matches!(
+            ColumnarReader::new(truncated),
+            Err(TaUtilsError::Format { offset: 16, .. })
+        ));
+        let mut corrupted = data.clone();
+        corrupted[HEADER_LEN + BLOCK_HEADER_LEN] ^= 1;
+        assert!(matches!(
+            ColumnarReader::new(&corrupted),
+            Err(TaUtilsError::Format { offset: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn appends_to_existing_files() {
+        let bars = bars(5);
+        let options = ColumnarOptions::new().set_block_size(2);
+        let data = write(options, &bars[..3]);
+        let mut writer = ColumnarWriter::append(Cursor::new(data), 2).unwrap();
+        assert_eq!(writer.len(), 3);
+        writer.write_all(&bars[3..]).unwrap();
+        let data = writer.into_inner().unwrap().into_inner();
+        assert_bars(&ColumnarReader::new(&data).unwrap(), &bars);
+    }
+}
@@ -0,0 +1,782 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Bar, Candle, MarketData, TaUtilsError, TaUtilsResult, Timeframe};
+
+/// A CSV column, either by header name (case insensitive) or by zero based index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Column {
+    Name(String),
+    Index(usize),
+}
+
+impl From<&str> for Column {
+    fn from(value: &str) -> Self {
+        Column::Name(value.to_string())
+    }
+}
+
+impl From<usize> for Column {
+    fn from(value: usize) -> Self {
+        Column::Index(value)
+    }
+}
+
+/// Mapping between CSV columns and bar fields.
+///
+/// When the file has a header, optional columns (`timestamp`, `price`, `volume`,
+/// `close_time`, `timeframe`) missing from it are treated as absent instead of failing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvColumns {
+    pub timestamp: Option<Column>,
+    pub open: Column,
+    pub high: Column,
+    pub low: Column,
+    pub close: Column,
+    /// Defaults to the close when absent.
+    pub price: Option<Column>,
+    pub volume: Option<Column>,
+    /// Read with the same [`TimestampFormat`] as the timestamp.
+    pub close_time: Option<Column>,
+    /// A [`Timeframe`] such as `5m` or `1h`.
+    pub timeframe: Option<Column>,
+}
+
+impl Default for CsvColumns {
+    /// Header based mapping matching the files written by [`CsvWriter`].
+    fn default() -> Self {
+        Self {
+            timestamp: Some("timestamp".into()),
+            open: "open".into(),
+            high: "high".into(),
+            low: "low".into(),
+            close: "close".into(),
+            price: Some("price".into()),
+            volume: Some("volume".into()),
+            close_time: Some("close_time".into()),
+            timeframe: Some("timeframe".into()),
+        }
+    }
+}
+
+impl CsvColumns {
+    /// Positional mapping for headerless `timestamp,open,high,low,close,volume` files.
+    pub fn positional() -> Self {
+        Self {
+            timestamp: Some(0.into()),
+            open: 1.into(),
+            high: 2.into(),
+            low: 3.into(),
+            close: 4.into(),
+            price: None,
+            volume: Some(5.into()),
+            close_time: None,
+            timeframe: None,
+        }
+    }
+}
+
+/// Representation of timestamps in a CSV file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimestampFormat {
+    /// Reads Unix seconds, Unix milliseconds (values from `1e11`) or RFC 3339 dates,
+    /// writes RFC 3339 dates, or Unix milliseconds outside of the years 0 to 9999
+    /// which are always read back as milliseconds.
+    #[default]
+    Auto,
+    UnixSeconds,
+    UnixMillis,
+    /// `YYYY-MM-DD[THH:MM[:SS[.fff]]][Z|±HH:MM]`, written in UTC with millisecond precision.
+    Rfc3339,
+}
+
+/// What to do with rows without volume.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MissingVolume {
+    /// Use `NaN`, the crate wide marker for an unknown volume.
+    #[default]
+    Nan,
+    Zero,
+    Error,
+}
+
+/// Options shared by [`CsvReader`] and [`CsvWriter`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    /// Whether the first row is a header, detected from the first row if `None`.
+    pub has_headers: Option<bool>,
+    pub columns: CsvColumns,
+    pub timestamp_format: TimestampFormat,
+    pub missing_volume: MissingVolume,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_headers: None,
+            columns: CsvColumns::default(),
+            timestamp_format: TimestampFormat::Auto,
+            missing_volume: MissingVolume::Nan,
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_delimiter(mut self, val: u8) -> Self {
+        self.delimiter = val;
+        self
+    }
+
+    pub fn set_has_headers(mut self, val: bool) -> Self {
+        self.has_headers = Some(val);
+        self
+    }
+
+    pub fn set_columns(mut self, val: CsvColumns) -> Self {
+        self.columns = val;
+        self
+    }
+
+    pub fn set_timestamp_format(mut self, val: TimestampFormat) -> Self {
+        self.timestamp_format = val;
+        self
+    }
+
+    pub fn set_missing_volume(mut self, val: MissingVolume) -> Self {
+        self.missing_volume = val;
+        self
+    }
+}
+
+/// Resolved column indices.
+#[derive(Debug, Clone)]
+struct Indices {
+    timestamp: Option<usize>,
+    open: usize,
+    high: usize,
+    low: usize,
+    close: usize,
+    price: Option<usize>,
+    volume: Option<usize>,
+    close_time: Option<usize>,
+    timeframe: Option<usize>,
+}
+
+/// Lazy iterator over the rows of an OHLCV CSV file, yielding one `MarketData::Bar` per row.
+pub struct CsvReader<R> {
+    reader: ::csv::Reader<R>,
+    options: CsvOptions,
+    indices: Option<Indices>,
+    record: ::csv::StringRecord,
+    /// First data row, read while detecting the header.
+    pending: Option<::csv::StringRecord>,
+    done: bool,
+}
+
+impl CsvReader<File> {
+    pub fn from_path(path: impl AsRef<Path>, options: CsvOptions) -> TaUtilsResult<Self> {
+        Ok(Self::new(File::open(path)?, options))
+    }
+}
+
+impl<R: io::Read> CsvReader<R> {
+    pub fn new(reader: R, options: CsvOptions) -> Self {
+        let reader = ::csv::ReaderBuilder::new()
+            .delimiter(options.delimiter)
+            .has_headers(false)
+            .flexible(true)
+            .trim(::csv::Trim::All)
+            .from_reader(reader);
+        Self {
+            reader,
+            options,
+            indices: None,
+            record: ::csv::StringRecord::new(),
+            pending: None,
+            done: false,
+        }
+    }
+
+    /// Reads the next raw record, returning `false` at the end of the file.
+    fn read_record(&mut self) -> TaUtilsResult<bool> {
+        let position = self.reader.position().clone();
+        self.reader
+            .read_record(&mut self.record)
+            .map_err(|err| csv_error(err, &position))
+    }
+
+    /// Reads the first row, detects the header and resolves the column indices.
+    fn init(&mut self) -> TaUtilsResult<()> {
+        if !self.read_record()? {
+            self.done = true;
+            return Ok(());
+        }
+        let first = self.record.clone();
+        let has_headers = self.options.has_headers.unwrap_or_else(|| {
+            first
+                .iter()
+                .all(|field| field.is_empty() || field.parse::<f64>().is_err())
+        });
+        let header = has_headers.then_some(&first);
+        let position = position_of(&first);
+        let columns = &self.options.columns;
+        self.indices = Some(Indices {
+            timestamp: resolve_optional(columns.timestamp.as_ref(), header, &position)?,
+            open: resolve(&columns.open, header, &position)?,
+            high: resolve(&columns.high, header, &position)?,
+            low: resolve(&columns.low, header, &position)?,
+            close: resolve(&columns.close, header, &position)?,
+            price: resolve_optional(columns.price.as_ref(), header, &position)?,
+            volume: resolve_optional(columns.volume.as_ref(), header, &position)?,
+            close_time: resolve_optional(columns.close_time.as_ref(), header, &position)?,
+            timeframe: resolve_optional(columns.timeframe.as_ref(), header, &position)?,
+        });
+        if !has_headers {
+            self.pending = Some(first);
+        }
+        Ok(())
+    }
+
+    fn parse(&self, record: &::csv::StringRecord) -> TaUtilsResult<MarketData> {
+        let Some(indices) = &self.indices else {
+            return Err(TaUtilsError::Unexpected(
+                "CSV columns are not resolved".to_string(),
+            ));
+        };
+        let position = position_of(record);
+        let field = |index: usize| record.get(index).unwrap_or("");
+        let number = |name: &str, index: usize| {
+            let value = field(index);
+            value
+                .parse::<f64>()
+                .map_err(|_| parse_error(&position, format!("invalid {name} '{value}'")))
+        };
+
+        let close = number("close", indices.close)?;
+        let mut bar = Bar::new()
+            .set_open(number("open", indices.open)?)
+            .set_high(number("high", indices.high)?)
+            .set_low(number("low", indices.low)?)
+            .set_close(close)
+            .set_price(close);
+        if let Some(index) = indices.price.filter(|&index| !field(index).is_empty()) {
+            bar.price = number("price", index)?;
+        }
+        bar.volume = match indices.volume.filter(|&index| !field(index).is_empty()) {
+            Some(index) => number("volume", index)?,
+            None => match self.options.missing_volume {
+                MissingVolume::Nan => f64::NAN,
+                MissingVolume::Zero => 0.0,
+                MissingVolume::Error => {
+                    return Err(parse_error(&position, "missing volume".to_string()));
+                }
+            },
+        };
+        let timestamp = |name: &str, index: Option<usize>| match index
+            .map(field)
+            .filter(|value| !value.is_empty())
+        {
+            Some(value) => parse_timestamp(value, self.options.timestamp_format)
+                .map(Some)
+                .ok_or_else(|| parse_error(&position, format!("invalid {name} '{value}'"))),
+            None => Ok(None),
+        };
+        bar.open_time = timestamp("timestamp", indices.timestamp)?;
+        bar.close_time = timestamp("close time", indices.close_time)?;
+        if let Some(index) = indices.timeframe.filter(|&index| !field(index).is_empty()) {
+            let value = field(index);
+            let timeframe = value
+                .parse::<Timeframe>()
+                .map_err(|_| parse_error(&position, format!("invalid timeframe '{value}'")))?;
+            bar.timeframe = Some(timeframe);
+        }
+        Ok(MarketData::Bar(bar))
+    }
+}
+
+impl<R: io::Read> Iterator for CsvReader<R> {
+    type Item = TaUtilsResult<MarketData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if self.indices.is_none() {
+            if let Err(err) = self.init() {
+                self.done = true;
+                return Some(Err(err));
+            }
+            if self.done {
+                return None;
+            }
+        }
+        if let Some(record) = self.pending.take() {
+            return Some(self.parse(&record));
+        }
+        match self.read_record() {
+            Ok(true) => Some(self.parse(&self.record)),
+            Ok(false) => {
+                self.done = true;
+                None
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+/// Writes candles as `timestamp,open,high,low,close,price,volume,close_time,timeframe`
+/// rows, readable back with the default [`CsvColumns`]. Values are written with
+/// their shortest exact representation so bars round trip without loss, unknown
+/// volumes, times and timeframes are left empty.
+pub struct CsvWriter<W: io::Write> {
+    writer: ::csv::Writer<W>,
+    options: CsvOptions,
+    header_written: bool,
+}
+
+impl CsvWriter<File> {
+    pub fn from_path(path: impl AsRef<Path>, options: CsvOptions) -> TaUtilsResult<Self> {
+        Ok(Self::new(File::create(path)?, options))
+    }
+}
+
+impl<W: io::Write> CsvWriter<W> {
+    pub fn new(writer: W, options: CsvOptions) -> Self {
+        let writer = ::csv::WriterBuilder::new()
+            .delimiter(options.delimiter)
+            .from_writer(writer);
+        Self {
+            writer,
+            // Nothing to detect when writing, a header is written unless disabled.
+            header_written: options.has_headers == Some(false),
+            options,
+        }
+    }
+
+    /// Writes a candle as the bar returned by [`Candle::to_bar`], so only the
+    /// bar fields of other candles are kept.
+    pub fn write(&mut self, candle: &dyn Candle) -> TaUtilsResult<()> {
+        if !self.header_written {
+            self.writer
+                .write_record([
+                    "timestamp",
+                    "open",
+                    "high",
+                    "low",
+                    "close",
+                    "price",
+                    "volume",
+                    "close_time",
+                    "timeframe",
+                ])
+                .map_err(|err| csv_error(err, &::csv::Position::new()))?;
+            self.header_written = true;
+        }
+        let bar = candle.to_bar();
+        let timestamp = |timestamp: Option<i64>| {
+            timestamp
+                .map(|timestamp| format_timestamp(timestamp, self.options.timestamp_format))
+                .unwrap_or_default()
+        };
+        let volume = if bar.volume.is_nan() {
+            String::new()
+        } else {
+            bar.volume.to_string()
+        };
+        self.writer
+            .write_record([
+                timestamp(bar.open_time),
+                bar.open.to_string(),
+                bar.high.to_string(),
+                bar.low.to_string(),
+                bar.close.to_string(),
+                bar.price.to_string(),
+                volume,
+                timestamp(bar.close_time),
+                bar.timeframe.map(|tf| tf.to_string()).unwrap_or_default(),
+            ])
+            .map_err(|err| csv_error(err, &::csv::Position::new()))
+    }
+
+    /// Writes market data that must be bars, trades, quotes and floats would
+    /// lose their own fields and are rejected.
+    pub fn write_all<'a>(
+        &mut self,
+        candles: impl IntoIterator<Item = &'a MarketData>,
+    ) -> TaUtilsResult<()> {
+        for candle in candles {
+            match candle {
+                MarketData::Bar(bar) => self.write(bar)?,
+                other => {
+                    return Err(TaUtilsError::InvalidParameter(format!(
+                        "CSV files only hold bars, got {other:?}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> TaUtilsResult<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(self) -> TaUtilsResult<W> {
+        self.writer
+            .into_inner()
+            .map_err(|err| TaUtilsError::Io(err.into_error()))
+    }
+}
+
+fn position_of(record: &::csv::StringRecord) -> ::csv::Position {
+    record
+        .position()
+        .cloned()
+        .unwrap_or_else(::csv::Position::new)
+}
+
+fn parse_error(position: &::csv::Position, message: String) -> TaUtilsError {
+    TaUtilsError::Parse {
+        line: position.line(),
+        offset: position.byte(),
+        message,
+    }
+}
+
+fn csv_error(err: ::csv::Error, position: &::csv::Position) -> TaUtilsError {
+    let position = err.position().unwrap_or(position).clone();
+    match err.into_kind() {
+        ::csv::ErrorKind::Io(err) => TaUtilsError::Io(err),
+        kind => parse_error(&position, format!("{kind:?}")),
+    }
+}
+
+fn resolve(
+    column: &Column,
+    header: Option<&::csv::StringRecord>,
+    position: &::csv::Position,
+) -> TaUtilsResult<usize> {
+    resolve_optional(Some(column), header, position)?.ok_or_else(|| {
+        parse_error(
+            position,
+            format!("column {column:?} not found in the header"),
+        )
+    })
+}
+
+fn resolve_optional(
+    column: Option<&Column>,
+    header: Option<&::csv::StringRecord>,
+    position: &::csv::Position,
+) -> TaUtilsResult<Option<usize>> {
+    match (column, header) {
+        (None, _) => Ok(None),
+        (Some(Column::Index(index)), _) => Ok(Some(*index)),
+        (Some(Column::Name(name)), Some(header)) => Ok(header
+            .iter()
+            .position(|field| field.eq_ignore_ascii_case(name))),
+        (Some(Column::Name(name)), None) => Err(parse_error(
+            position,
+            format!("column '{name}' is selected by name but the file has no header"),
+        )),
+    }
+}
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+fn parse_timestamp(value: &str, format: TimestampFormat) -> Option<i64> {
+    match format {
+        TimestampFormat::UnixSeconds => value
+            .parse::<f64>()
+            .ok()
+            .and_then(|s| round_millis(s * 1000.0)),
+        TimestampFormat::UnixMillis => value
+            .parse::<i64>()
+            .ok()
+            .or_else(|| value.parse::<f64>().ok().and_then(round_millis)),
+        TimestampFormat::Rfc3339 => parse_rfc3339(value),
+        TimestampFormat::Auto => match value.parse::<f64>() {
+            Ok(number) if number.abs() >= 1e11 => {
+                parse_timestamp(value, TimestampFormat::UnixMillis)
+            }
+            Ok(_) => parse_timestamp(value, TimestampFormat::UnixSeconds),
+            Err(_) => parse_rfc3339(value),
+        },
+    }
+}
+
+/// Rounds a number of milliseconds, `None` if it is not finite or does not fit in an `i64`.
+fn round_millis(millis: f64) -> Option<i64> {
+    let millis = millis.round();
+    (i64::MIN as f64..i64::MAX as f64)
+        .contains(&millis)
+        .then_some(millis as i64)
+}
+
+/// Range of the timestamps written as RFC 3339 dates by [`TimestampFormat::Auto`],
+/// from 0000-01-01 to 9999-12-31 included.
+const RFC3339_RANGE: core::ops::Range<i64> = -62_167_219_200_000..253_402_300_800_000;
+
+fn format_timestamp(timestamp: i64, format: TimestampFormat) -> String {
+    match format {
+        TimestampFormat::Auto if RFC3339_RANGE.contains(&timestamp) => format_rfc3339(timestamp),
+        TimestampFormat::Auto | TimestampFormat::UnixMillis => timestamp.to_string(),
+        TimestampFormat::UnixSeconds => (timestamp as f64 / 1000.0).to_string(),
+        TimestampFormat::Rfc3339 => format_rfc3339(timestamp),
+    }
+}
+
+/// Parses `YYYY-MM-DD[THH:MM[:SS[.fff]]][Z|±HH:MM]` into milliseconds since the Unix epoch.
+fn parse_rfc3339(value: &str) -> Option<i64> {
+    fn number(s: &str) -> Option<i64> {
+        (!s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| s.parse().ok())
+            .flatten()
+    }
+
+    // Every separator is ASCII, this keeps the byte slicing below on char boundaries.
+    if !value.is_ascii() {
+        return None;
+    }
+    let (date, time) = match value.find(['T', 't', ' ']) {
+        Some(split) => (&value[..split], &value[split + 1..]),
+        None => (value, ""),
+    };
+    let mut parts = date.splitn(3, '-');
+    let year = number(parts.next()?)?;
+    let month = number(parts.next()?)?;
+    let day = number(parts.next()?)?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(split) => (&time[..split], &time[split..]),
+        None => (time, ""),
+    };
+    let mut millis = 0;
+    if !time.is_empty() {
+        let (clock, fraction) = match time.split_once('.') {
+            Some((clock, fraction)) => (clock, Some(fraction)),
+            None => (time, None),
+        };
+        let mut parts = clock.split(':');
+        let hours = number(parts.next()?)?;
+        let minutes = number(parts.next()?)?;
+        let seconds = parts.next().map(number).unwrap_or(Some(0))?;
+        if parts.next().is_some() || hours > 23 || minutes > 59 || seconds > 60 {
+            return None;
+        }
+        millis = ((hours * 60 + minutes) * 60 + seconds) * 1000;
+        if let Some(fraction) = fraction {
+            number(fraction)?;
+            let digits = format!("{fraction:0<3}");
+            millis += digits[..3].parse::<i64>().ok()?;
+        }
+    }
+
+    let offset = match offset {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let offset = offset[1..].replace(':', "");
+            if offset.len() != 4 {
+                return None;
+            }
+            let hours = number(&offset[..2])?;
+            let minutes = number(&offset[2..])?;
+            sign * (hours * 60 + minutes) * 60_000
+        }
+    };
+
+    days_from_civil(year, month, day)?
+        .checked_mul(MILLIS_PER_DAY)?
+        .checked_add(millis - offset)
+}
+
+fn format_rfc3339(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(MILLIS_PER_DAY);
+    let millis = timestamp.rem_euclid(MILLIS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    let (seconds, millis) = (millis / 1000, millis % 1000);
+    let clock = format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if millis == 0 {
+        format!("{clock}Z")
+    } else {
+        format!("{clock}.{millis:03}Z")
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since the Unix epoch of a proleptic Gregorian date (Howard Hinnant's algorithm),
+/// `None` if it does not fit in an `i64`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era.checked_mul(146_097)?.checked_add(day_of_era - 719_468)
+}
+
+/// Proleptic Gregorian date of a number of days since the Unix epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(open_time: i64) -> MarketData {
+        let mut bar = Bar::new()
+            .set_open(1.0)
+            .set_high(2.5)
+            .set_low(0.1)
+            .set_close(1.3)
+            .set_price(1.3)
+            .set_volume(10.0)
+            .set_open_time(open_time);
+        bar.timeframe = Some(Timeframe::minutes(1).unwrap());
+        bar.close_time = Some(open_time + 59_999);
+        MarketData::Bar(bar)
+    }
+
+    fn round_trip(candles: &[MarketData], options: CsvOptions) -> Vec<MarketData> {
+        let mut writer = CsvWriter::new(Vec::new(), options.clone());
+        writer.write_all(candles).unwrap();
+        let data = writer.into_inner().unwrap();
+        CsvReader::new(data.as_slice(), options)
+            .collect::<TaUtilsResult<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn round_trips_with_every_timestamp_format() {
+        let candles: Vec<_> = [
+            0,
+            60_000,
+            -86_400_000,
+            1_709_206_200_500,
+            RFC3339_RANGE.start,
+            RFC3339_RANGE.end - 60_000,
+            RFC3339_RANGE.end,
+            -1_000_000_000_000_000,
+            i64::MAX - 59_999,
+        ]
+        .into_iter()
+        .map(bar)
+        .collect();
+        assert_eq!(round_trip(&candles, CsvOptions::new()), candles);
+        let millis = CsvOptions::new().set_timestamp_format(TimestampFormat::UnixMillis);
+        assert_eq!(round_trip(&candles, millis), candles);
+        let rfc3339 = CsvOptions::new().set_timestamp_format(TimestampFormat::Rfc3339);
+        assert_eq!(round_trip(&candles[..7], rfc3339), &candles[..7]);
+    }
+
+    #[test]
+    fn round_trips_missing_values() {
+        let candles = vec![MarketData::Bar(
+            Bar::new()
+                .set_open(1.0)
+                .set_high(1.0)
+                .set_low(1.0)
+                .set_close(1.0)
+                .set_price(1.0)
+                .set_volume(f64::NAN),
+        )];
+        let back = round_trip(&candles, CsvOptions::new());
+        assert!(back[0].volume().is_nan());
+        assert_eq!(back[0].timestamp(), None);
+        assert_eq!(back[0].timeframe(), None);
+        assert_eq!(back[0].to_bar().close_time, None);
+    }
+
+    #[test]
+    fn rejects_lossy_market_data() {
+        let mut writer = CsvWriter::new(Vec::new(), CsvOptions::new());
+        assert!(writer.write_all([&MarketData::Float(1.0)]).is_err());
+    }
+
+    #[test]
+    fn rejects_non_finite_timestamps() {
+        for format in [
+            TimestampFormat::Auto,
+            TimestampFormat::UnixSeconds,
+            TimestampFormat::UnixMillis,
+        ] {
+            for value in ["NaN", "inf", "-inf", "1e300"] {
+                assert_eq!(parse_timestamp(value, format), None, "{value} {format:?}");
+            }
+        }
+        assert_eq!(parse_timestamp("1.5", TimestampFormat::UnixSeconds), Some(1500));
+    }
+
+    #[test]
+    fn parses_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01"), Some(0));
+        assert_eq!(parse_rfc3339("2024-02-29T12:30:00.5+01:00"), Some(1_709_206_200_500));
+        assert_eq!(parse_rfc3339("2000-02-29"), Some(951_782_400_000));
+        assert_eq!(parse_rfc3339("0000-01-01T00:00:00Z"), Some(RFC3339_RANGE.start));
+        assert_eq!(parse_rfc3339("10000-01-01T00:00:00Z"), Some(RFC3339_RANGE.end));
+        for invalid in [
+            "2024-02-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-01-00",
+            "2024-01-01T24:00",
+            "2024-01-01T00:00+é1:00",
+            "2024-01-01T00:00+0é:00",
+            "9223372036854775807-01-01",
+        ] {
+            assert_eq!(parse_rfc3339(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn formats_rfc3339() {
+        assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_rfc3339(1_709_202_600_500), "2024-02-29T10:30:00.500Z");
+        assert_eq!(format_rfc3339(-1), "1969-12-31T23:59:59.999Z");
+    }
+}
@@ -0,0 +1,348 @@
+use std::io::{self, BufRead, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{MarketData, OutputType, TaUtilsError, TaUtilsResult};
+
+/// An indicator input paired with the output it produced, the unit of a recorded session.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub input: MarketData,
+    pub output: OutputType,
+}
+
+impl From<(MarketData, OutputType)> for Record {
+    fn from((input, output): (MarketData, OutputType)) -> Self {
+        Self { input, output }
+    }
+}
+
+impl From<Record> for (MarketData, OutputType) {
+    fn from(record: Record) -> Self {
+        (record.input, record.output)
+    }
+}
+
+/// Position in an NDJSON stream, used to resume reading where a previous reader stopped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NdjsonPosition {
+    /// Byte offset of the next line.
+    pub offset: u64,
+    /// 1 based number of the next line.
+    pub line: u64,
+}
+
+impl NdjsonPosition {
+    pub fn start() -> Self {
+        Self { offset: 0, line: 1 }
+    }
+}
+
+/// Lazy reader of newline delimited JSON values, one `T` per line.
+///
+/// Blank lines are skipped. A malformed line yields a `TaUtilsError::Parse` with
+/// its line number and byte offset, and reading carries on with the next line.
+/// A last line without a trailing newline is treated as still being written: it
+/// is only read once terminated, and [`NdjsonReader::position`] stays at its start.
+pub struct NdjsonReader<R, T = MarketData> {
+    reader: R,
+    position: NdjsonPosition,
+    /// The line being read, kept between calls while it is not terminated.
+    buf: Vec<u8>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<R: BufRead, T: DeserializeOwned> NdjsonReader<R, T> {
+    pub fn new(reader: R) -> Self {
+        Self::resume(reader, NdjsonPosition::start())
+    }
+
+    /// Creates a reader continuing from `position`, `reader` must already be at `position.offset`.
+    pub fn resume(reader: R, position: NdjsonPosition) -> Self {
+        Self {
+            reader,
+            position,
+            buf: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Position of the next line to be read, to be stored to resume reading later.
+    pub fn position(&self) -> NdjsonPosition {
+        self.position
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next value, `None` at the end of the stream or of the terminated lines.
+    pub fn read(&mut self) -> Option<TaUtilsResult<T>> {
+        loop {
+            // Read bytes rather than a `String` so the position still moves past
+            // lines that are not valid UTF-8.
+            if let Err(err) = self.reader.read_until(b'\n', &mut self.buf) {
+                return Some(Err(err.into()));
+            }
+            if !self.buf.ends_with(b"\n") {
+                return None;
+            }
+            if let Some(value) = self.parse_line() {
+                return Some(value);
+            }
+        }
+    }
+
+    /// Reads the value of a last line left without a trailing newline, for streams
+    /// known to be complete. `None` if there is no such line.
+    pub fn finish(&mut self) -> Option<TaUtilsResult<T>> {
+        if let Err(err) = self.reader.read_to_end(&mut self.buf) {
+            return Some(Err(err.into()));
+        }
+        if self.buf.is_empty() {
+            return None;
+        }
+        self.parse_line()
+    }
+
+    /// Parses the line in the buffer and moves past it, `None` for a blank line.
+    fn parse_line(&mut self) -> Option<TaUtilsResult<T>> {
+        let start = self.position;
+        self.position.offset += self.buf.len() as u64;
+        self.position.line += 1;
+        let parse_error = |message: String| TaUtilsError::Parse {
+            line: start.line,
+            offset: start.offset,
+            message,
+        };
+        let value = match std::str::from_utf8(&self.buf) {
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => {
+                Some(serde_json::from_str(line).map_err(|err| parse_error(err.to_string())))
+            }
+            Err(err) => Some(Err(parse_error(err.to_string()))),
+        };
+        self.buf.clear();
+        value
+    }
+}
+
+impl<R: BufRead + Seek, T: DeserializeOwned> NdjsonReader<R, T> {
+    /// Moves the reader to a position previously returned by [`NdjsonReader::position`].
+    pub fn seek(&mut self, position: NdjsonPosition) -> TaUtilsResult<()> {
+        self.reader.seek(SeekFrom::Start(position.offset))?;
+        self.position = position;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+impl<R: BufRead, T: DeserializeOwned> Iterator for NdjsonReader<R, T> {
+    type Item = TaUtilsResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read()
+    }
+}
+
+/// Incremental writer of newline delimited JSON values.
+pub struct NdjsonWriter<W: Write> {
+    writer: W,
+    position: NdjsonPosition,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self::resume(writer, NdjsonPosition::start())
+    }
+
+    /// Creates a writer appending to a stream already containing data up to `position`.
+    pub fn resume(writer: W, position: NdjsonPosition) -> Self {
+        Self { writer, position }
+    }
+
+    /// Position right after the last written line.
+    pub fn position(&self) -> NdjsonPosition {
+        self.position
+    }
+
+    pub fn write<T: Serialize + ?Sized>(&mut self, value: &T) -> TaUtilsResult<()> {
+        let mut line = serde_json::to_vec(value)
+            .map_err(|err| TaUtilsError::Unexpected(format!("Serialization error, {err}")))?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.position.offset += line.len() as u64;
+        self.position.line += 1;
+        Ok(())
+    }
+
+    /// Writes an input and the output it produced as a [`Record`].
+    pub fn write_record(&mut self, input: &MarketData, output: &OutputType) -> TaUtilsResult<()> {
+        #[derive(Serialize)]
+        struct RecordRef<'a> {
+            input: &'a MarketData,
+            output: &'a OutputType,
+        }
+
+        self.write(&RecordRef { input, output })
+    }
+
+    pub fn flush(&mut self) -> TaUtilsResult<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(mut self) -> TaUtilsResult<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads every value of an NDJSON stream, failing on the first malformed line.
+pub fn read_all<T: DeserializeOwned>(reader: impl io::Read) -> TaUtilsResult<Vec<T>> {
+    let mut reader = NdjsonReader::<_, T>::new(io::BufReader::new(reader));
+    let mut values = reader.by_ref().collect::<TaUtilsResult<Vec<_>>>()?;
+    if let Some(value) = reader.finish() {
+        values.push(value?);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bar;
+    use chipa_types::Number;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record::from((
+                MarketData::Float(1.5),
+                OutputType::Single(Number::float(2.0)),
+            )),
+            Record::from((
+                MarketData::Bar(Bar::new().set_close(3.0).set_volume(f64::NAN)),
+                OutputType::Array(vec![Number::float(f64::NAN), Number::float(1.0)]),
+            )),
+            Record::from((
+                MarketData::Float(f64::NAN),
+                OutputType::Single(Number::float(f64::NAN)),
+            )),
+        ]
+    }
+
+    fn write(records: &[Record]) -> Vec<u8> {
+        let mut writer = NdjsonWriter::new(Vec::new());
+        for record in records {
+            writer.write(record).unwrap();
+        }
+        writer.into_inner().unwrap()
+    }
+
+    /// Compares through JSON as `NaN` is not equal to itself.
+    fn json<T: Serialize>(value: &T) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    #[test]
+    fn round_trips_records_with_nan() {
+        let records = records();
+        let back: Vec<Record> = read_all(write(&records).as_slice()).unwrap();
+        assert_eq!(json(&back), json(&records));
+        let OutputType::Single(value) = &back[2].output else {
+            panic!("{:?}", back[2].output);
+        };
+        assert!(value.as_f64().is_nan());
+    }
+
+    #[test]
+    fn waits_for_unterminated_lines() {
+        let data = write(&records());
+        let cut = data.len() - 5;
+        let mut reader = NdjsonReader::<_, Record>::new(io::Cursor::new(data[..cut].to_vec()));
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        let before = reader.position();
+        assert!(reader.next().is_none());
+        assert!(reader.next().is_none());
+        assert_eq!(reader.position(), before);
+
+        // A new reader resumed at the position gets the whole line once it is written.
+        let mut resumed = NdjsonReader::<_, Record>::resume(
+            io::Cursor::new(data[before.offset as usize..].to_vec()),
+            before,
+        );
+        assert!(resumed.next().unwrap().is_ok());
+        assert!(resumed.next().is_none());
+        assert_eq!(resumed.position().line, 4);
+        assert_eq!(resumed.position().offset, data.len() as u64);
+    }
+
+    /// A source that more data is appended to, like a file being written.
+    #[derive(Clone, Default)]
+    struct Growing(std::rc::Rc<std::cell::RefCell<std::collections::VecDeque<u8>>>);
+
+    impl io::Read for Growing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.borrow_mut().read(buf)
+        }
+    }
+
+    #[test]
+    fn continues_unterminated_lines() {
+        let data = write(&records());
+        let cut = data.len() - 5;
+        let source = Growing::default();
+        source.0.borrow_mut().extend(&data[..cut]);
+        let mut reader = NdjsonReader::<_, Record>::new(io::BufReader::new(source.clone()));
+        assert_eq!(reader.by_ref().count(), 2);
+        source.0.borrow_mut().extend(&data[cut..]);
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(reader.position().offset, data.len() as u64);
+    }
+
+    #[test]
+    fn finishes_complete_streams() {
+        let data = b"1.0\n\n2.0";
+        let mut reader = NdjsonReader::<_, f64>::new(&data[..]);
+        assert_eq!(reader.next().unwrap().unwrap(), 1.0);
+        assert!(reader.next().is_none());
+        assert_eq!(reader.finish().unwrap().unwrap(), 2.0);
+        assert_eq!(reader.position(), NdjsonPosition { offset: 8, line: 4 });
+        assert!(reader.finish().is_none());
+        assert_eq!(read_all::<f64>(&data[..]).unwrap(), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn skips_invalid_lines() {
+        let data = b"1.0\n\xff\xfe\n{\n2.0\n";
+        let mut reader = NdjsonReader::<_, f64>::new(&data[..]);
+        assert_eq!(reader.next().unwrap().unwrap(), 1.0);
+        assert!(matches!(
+            reader.next().unwrap(),
+            Err(TaUtilsError::Parse {
+                line: 2,
+                offset: 4,
+                ..
+            })
+        ));
+        assert!(matches!(
+            reader.next().unwrap(),
+            Err(TaUtilsError::Parse {
+                line: 3,
+                offset: 7,
+                ..
+            })
+        ));
+        assert_eq!(reader.next().unwrap().unwrap(), 2.0);
+        assert_eq!(
+            reader.position(),
+            NdjsonPosition {
+                offset: 13,
+                line: 5
+            }
+        );
+    }
+}
@@ -1,12 +1,38 @@
+pub mod adapter;
 pub mod errors;
+pub mod io;
+mod ops;
 pub mod output;
+pub mod params;
+pub mod patterns;
+pub mod resample;
+pub mod sanitize;
+pub mod tolerance;
+pub mod transform;
 pub mod traits;
 pub mod types;
+pub mod warmup;
 
-pub use errors::{OutputError, TaUtilsError, TaUtilsResult};
+pub use adapter::{Indicator, IntoInput, NextExt};
+#[cfg(feature = "stream")]
+pub use adapter::{IndicatorStream, NextStreamExt};
+pub use errors::{BarError, OutputError, TaUtilsError, TaUtilsResult};
 pub use output::{OutputShape, OutputType, Statics};
-pub use traits::{Candle, IndicatorTrait, Next, Period, Reset};
-pub use types::{Bar, MarketData, Queue};
+pub use params::{ParamDescriptor, ParamKind, ParamMap, ParamValue, Parameters};
+pub use resample::Resampler;
+pub use sanitize::{SanitizePolicy, Sanitizer};
+pub use tolerance::{Approx, NanPolicy, Tolerance};
+pub use transform::{BoxSize, HeikinAshi, RangeBars, Renko, Transformed};
+pub use traits::{
+    Candle, Checkpoint, IndicatorTrait, Next, NextBatch, Peek, Period, Readiness, Reset, Snapshot,
+    Update,
+};
+pub use types::{
+    Bar, MarketData, Moments, Queue, Quote, RollingCorrelation, RollingCovariance, RollingExtrema,
+    RollingMean, RollingStats, RollingStdDev, RollingSum, RollingVariance, RollingZScore, Side,
+    Timeframe, Trade,
+};
+pub use warmup::Warmup;
 
 #[cfg(feature = "strategy")] 
 pub mod strategy_error;
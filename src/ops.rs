@@ -0,0 +1,240 @@
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::{OutputError, OutputType, TaUtilsResult};
+use chipa_types::Number;
+
+/// Element-wise arithmetic with NumPy style broadcasting.
+///
+/// - a `Single` is broadcast to every element of the other operand,
+/// - `Array` and `Custom` operands must have the same length, unless one of them
+///   has a single element which is then broadcast,
+/// - an `Array` combined with a `Custom` is treated as a `Custom` of `Single`s,
+/// - `Named` operands must have the same fields in the same order, they only
+///   broadcast with a `Single`,
+/// - a `Missing` operand is broadcast like a `Single`, every element of the
+///   result is `Missing` (an `Array` gives a `Custom` of `Missing`).
+///
+/// Candle fields (`Open`, `Close`, ...) must be [`OutputType::resolve`]d first,
+/// they and `Static`/`Statics` values fail with [`OutputError::TypeMismatch`].
+/// Incompatible lengths fail with [`OutputError::LengthMismatch`], and the
+/// result is checked with [`OutputType::output_shape`], so empty operands fail
+/// with [`OutputError::InvalidOutputShape`].
+impl OutputType {
+    /// Absolute value of every element.
+    pub fn abs(&self) -> TaUtilsResult<OutputType> {
+        self.map(f64::abs)
+    }
+
+    /// Element-wise minimum, `NaN` elements are ignored like with [`f64::min`].
+    pub fn min(&self, other: &OutputType) -> TaUtilsResult<OutputType> {
+        self.zip_with(other, f64::min)
+    }
+
+    /// Element-wise maximum, `NaN` elements are ignored like with [`f64::max`].
+    pub fn max(&self, other: &OutputType) -> TaUtilsResult<OutputType> {
+        self.zip_with(other, f64::max)
+    }
+
+    fn map(&self, op: fn(f64) -> f64) -> TaUtilsResult<OutputType> {
+        checked(self.map_unchecked(op)?)
+    }
+
+    fn zip_with(&self, other: &OutputType, op: fn(f64, f64) -> f64) -> TaUtilsResult<OutputType> {
+        checked(self.zip_unchecked(other, op)?)
+    }
+
+    fn map_unchecked(&self, op: fn(f64) -> f64) -> TaUtilsResult<OutputType> {
+        match self {
+            OutputType::Missing => Ok(OutputType::Missing),
+            OutputType::Single(value) => Ok(OutputType::Single(apply(*value, op))),
+            OutputType::Array(values) => Ok(OutputType::Array(
+                values.iter().map(|value| apply(*value, op)).collect(),
+            )),
+            OutputType::Custom(values) => values
+                .iter()
+                .map(|value| value.map_unchecked(op))
+                .collect::<TaUtilsResult<_>>()
+                .map(OutputType::Custom),
+            OutputType::Named(fields) => fields
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), value.map_unchecked(op)?)))
+                .collect::<TaUtilsResult<_>>()
+                .map(OutputType::Named),
+            _ => Err(OutputError::TypeMismatch.into()),
+        }
+    }
+
+    /// `Missing` in every element of `self`, keeping its shape.
+    fn missing_like(&self) -> TaUtilsResult<OutputType> {
+        match self {
+            OutputType::Missing | OutputType::Single(_) => Ok(OutputType::Missing),
+            OutputType::Array(values) => {
+                Ok(OutputType::Custom(vec![OutputType::Missing; values.len()]))
+            }
+            OutputType::Custom(values) => values
+                .iter()
+                .map(OutputType::missing_like)
+                .collect::<TaUtilsResult<_>>()
+                .map(OutputType::Custom),
+            OutputType::Named(fields) => fields
+                .iter()
+                .map(|(name, value)| Ok((name.clone(), value.missing_like()?)))
+                .collect::<TaUtilsResult<_>>()
+                .map(OutputType::Named),
+            _ => Err(OutputError::TypeMismatch.into()),
+        }
+    }
+
+    fn zip_unchecked(
+        &self,
+        other: &OutputType,
+        op: fn(f64, f64) -> f64,
+    ) -> TaUtilsResult<OutputType> {
+        match (self, other) {
+            (OutputType::Missing, other) | (other, OutputType::Missing) => other.missing_like(),
+            (OutputType::Single(a), OutputType::Single(b)) => {
+                Ok(OutputType::Single(combine(*a, *b, op)))
+            }
+            (OutputType::Single(a), OutputType::Array(b)) => Ok(OutputType::Array(
+                b.iter().map(|b| combine(*a, *b, op)).collect(),
+            )),
+            (OutputType::Array(a), OutputType::Single(b)) => Ok(OutputType::Array(
+                a.iter().map(|a| combine(*a, *b, op)).collect(),
+            )),
+            (OutputType::Array(a), OutputType::Array(b)) => {
+                broadcast(a, b, |a, b| Ok(combine(*a, *b, op))).map(OutputType::Array)
+            }
+            (OutputType::Custom(a), OutputType::Custom(b)) => {
+                broadcast(a, b, |a, b| a.zip_unchecked(b, op)).map(OutputType::Custom)
+            }
+            (OutputType::Custom(a), OutputType::Single(_)) => a
+                .iter()
+                .map(|a| a.zip_unchecked(other, op))
+                .collect::<TaUtilsResult<_>>()
+                .map(OutputType::Custom),
+            (OutputType::Single(_), OutputType::Custom(b)) => b
+                .iter()
+                .map(|b| self.zip_unchecked(b, op))
+                .collect::<TaUtilsResult<_>>()
+                .map(OutputType::Custom),
+            (OutputType::Custom(a), OutputType::Array(b)) => {
+                broadcast(a, b, |a, b| a.zip_unchecked(&OutputType::Single(*b), op))
+                    .map(OutputType::Custom)
+            }
+            (OutputType::Array(a), OutputType::Custom(b)) => {
+                broadcast(a, b, |a, b| OutputType::Single(*a).zip_unchecked(b, op))
+                    .map(OutputType::Custom)
+            }
+            (OutputType::Named(a), OutputType::Named(b)) => {
+                if a.len() != b.len() {
+                    return Err(OutputError::LengthMismatch(a.len(), b.len()).into());
+                }
+                a.iter()
+                    .zip(b)
+                    .map(|((a_name, a), (b_name, b))| {
+                        if a_name != b_name {
+                            return Err(OutputError::TypeMismatch.into());
+                        }
+                        Ok((a_name.clone(), a.zip_unchecked(b, op)?))
+                    })
+                    .collect::<TaUtilsResult<_>>()
+                    .map(OutputType::Named)
+            }
+            (OutputType::Named(a), OutputType::Single(_)) => a
+                .iter()
+                .map(|(name, a)| Ok((name.clone(), a.zip_unchecked(other, op)?)))
+                .collect::<TaUtilsResult<_>>()
+                .map(OutputType::Named),
+            (OutputType::Single(_), OutputType::Named(b)) => b
+                .iter()
+                .map(|(name, b)| Ok((name.clone(), self.zip_unchecked(b, op)?)))
+                .collect::<TaUtilsResult<_>>()
+                .map(OutputType::Named),
+            _ => Err(OutputError::TypeMismatch.into()),
+        }
+    }
+}
+
+fn apply(value: Number, op: fn(f64) -> f64) -> Number {
+    Number::float(op(value.as_f64()))
+}
+
+fn combine(a: Number, b: Number, op: fn(f64, f64) -> f64) -> Number {
+    Number::float(op(a.as_f64(), b.as_f64()))
+}
+
+/// Pairs the elements of `a` and `b`, repeating the only element of a length 1 side.
+fn broadcast<A, B, R>(
+    a: &[A],
+    b: &[B],
+    mut op: impl FnMut(&A, &B) -> TaUtilsResult<R>,
+) -> TaUtilsResult<Vec<R>> {
+    match (a.len(), b.len()) {
+        (x, y) if x == y => a.iter().zip(b).map(|(a, b)| op(a, b)).collect(),
+        (1, _) => b.iter().map(|b| op(&a[0], b)).collect(),
+        (_, 1) => a.iter().map(|a| op(a, &b[0])).collect(),
+        (x, y) => Err(OutputError::LengthMismatch(x, y).into()),
+    }
+}
+
+fn checked(output: OutputType) -> TaUtilsResult<OutputType> {
+    output.output_shape()?;
+    Ok(output)
+}
+
+macro_rules! binary_op {
+    ($trait:ident, $method:ident, $op:expr) => {
+        impl $trait<&OutputType> for &OutputType {
+            type Output = TaUtilsResult<OutputType>;
+
+            fn $method(self, rhs: &OutputType) -> Self::Output {
+                self.zip_with(rhs, $op)
+            }
+        }
+
+        impl $trait for OutputType {
+            type Output = TaUtilsResult<OutputType>;
+
+            fn $method(self, rhs: OutputType) -> Self::Output {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl $trait<&OutputType> for OutputType {
+            type Output = TaUtilsResult<OutputType>;
+
+            fn $method(self, rhs: &OutputType) -> Self::Output {
+                (&self).$method(rhs)
+            }
+        }
+
+        impl $trait<OutputType> for &OutputType {
+            type Output = TaUtilsResult<OutputType>;
+
+            fn $method(self, rhs: OutputType) -> Self::Output {
+                self.$method(&rhs)
+            }
+        }
+    };
+}
+
+binary_op!(Add, add, |a, b| a + b);
+binary_op!(Sub, sub, |a, b| a - b);
+binary_op!(Mul, mul, |a, b| a * b);
+binary_op!(Div, div, |a, b| a / b);
+
+impl Neg for &OutputType {
+    type Output = TaUtilsResult<OutputType>;
+
+    fn neg(self) -> Self::Output {
+        self.map(|value| -value)
+    }
+}
+
+impl Neg for OutputType {
+    type Output = TaUtilsResult<OutputType>;
+
+    fn neg(self) -> Self::Output {
+        -&self
+    }
+}
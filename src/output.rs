@@ -1,8 +1,8 @@
 use core::fmt;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::{Candle, OutputError, TaUtilsError, TaUtilsResult};
+use crate::{Candle, OutputError, TaUtilsError, TaUtilsResult, Tolerance};
 use chipa_types::Number;
 
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
@@ -10,6 +10,15 @@ use chipa_types::Number;
 pub enum OutputShape {
     Shape(usize), // Normal shape, using enum in case in the future we want to add more shapes
     Tensor(Vec<Box<OutputShape>>),
+    /// Shape of a [`OutputType::Named`] output, fields keep their order.
+    Named(
+        #[serde(with = "named")]
+        #[cfg_attr(
+            feature = "schemars",
+            schemars(with = "std::collections::BTreeMap<String, OutputShape>")
+        )]
+        Vec<(String, OutputShape)>,
+    ),
 }
 
 impl fmt::Display for OutputShape {
@@ -26,6 +35,16 @@ impl fmt::Display for OutputShape {
                 }
                 write!(f, ")")
             }
+            OutputShape::Named(fields) => {
+                write!(f, "Named(")?;
+                for (i, (name, shape)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {shape}")?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
@@ -67,8 +86,8 @@ impl PartialOrd<Number> for Statics {
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum OutputType {
-    Single(Number),
-    Array(Vec<Number>),
+    Single(#[serde(deserialize_with = "nan_if_null")] Number),
+    Array(#[serde(deserialize_with = "nans_if_null")] Vec<Number>),
     Open,
     Close,
     High,
@@ -77,6 +96,24 @@ pub enum OutputType {
     Custom(Vec<OutputType>),
     Static(Statics),
     Statics(Vec<Statics>),
+    /// Named values of a multi-output indicator, e.g. the `macd`, `signal` and
+    /// `histogram` lines of a MACD. Serialized as a map keeping the field order.
+    Named(
+        #[serde(with = "named")]
+        #[cfg_attr(
+            feature = "schemars",
+            schemars(with = "std::collections::BTreeMap<String, OutputType>")
+        )]
+        Vec<(String, OutputType)>,
+    ),
+    /// A value that is not available, e.g. during warm-up or after invalid data.
+    ///
+    /// Stands for a single value in `output_shape` and propagates through
+    /// arithmetic. It equals itself with `==`, which compares structure, but it is
+    /// never ordered, so conditions built on `partial_cmp` stay false until the
+    /// value is available. Inside a `Custom` it masks individual elements, see
+    /// [`OutputType::mask_nan`].
+    Missing,
 }
 
 impl OutputType {
@@ -100,10 +137,123 @@ impl OutputType {
             .validate(),
             OutputType::Static(_) => Ok(OutputShape::Shape(1)),
             OutputType::Statics(vec) => OutputShape::Shape(vec.len()).validate(),
+            OutputType::Missing => Ok(OutputShape::Shape(1)),
+            OutputType::Named(fields) => OutputShape::Named(
+                fields
+                    .iter()
+                    .map(|(name, o)| Ok((name.clone(), o.output_shape()?)))
+                    .collect::<TaUtilsResult<Vec<(String, OutputShape)>>>()?,
+            )
+            .validate(),
+        }
+    }
+
+    pub fn is_missing(&self) -> bool {
+        matches!(self, OutputType::Missing)
+    }
+
+    /// Returns `true` if the output or any nested element is missing.
+    pub fn has_missing(&self) -> bool {
+        match self {
+            OutputType::Missing => true,
+            OutputType::Custom(vec) => vec.iter().any(OutputType::has_missing),
+            OutputType::Named(fields) => fields.iter().any(|(_, o)| o.has_missing()),
+            _ => false,
+        }
+    }
+
+    /// Replaces `NaN` values with [`OutputType::Missing`].
+    ///
+    /// An `Array` holding `NaN` values becomes a `Custom` of `Single` and
+    /// `Missing` elements, so the shape of the output is preserved.
+    pub fn mask_nan(self) -> OutputType {
+        let is_nan = |n: &Number| n.as_f64().is_nan();
+        match self {
+            OutputType::Single(n) if is_nan(&n) => OutputType::Missing,
+            OutputType::Array(vec) if vec.iter().any(is_nan) => OutputType::Custom(
+                vec.into_iter()
+                    .map(|n| OutputType::Single(n).mask_nan())
+                    .collect(),
+            ),
+            OutputType::Custom(vec) => {
+                OutputType::Custom(vec.into_iter().map(OutputType::mask_nan).collect())
+            }
+            OutputType::Named(fields) => OutputType::Named(
+                fields
+                    .into_iter()
+                    .map(|(name, o)| (name, o.mask_nan()))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Builds a [`OutputType::Named`] output from `(name, value)` pairs.
+    pub fn named<S: Into<String>>(fields: impl IntoIterator<Item = (S, OutputType)>) -> Self {
+        OutputType::Named(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name.into(), value))
+                .collect(),
+        )
+    }
+
+    /// Field `name` of a `Named` output, `None` for any other variant.
+    pub fn field(&self, name: &str) -> Option<&OutputType> {
+        match self {
+            OutputType::Named(fields) => fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Names of the fields of a `Named` output, in order.
+    pub fn field_names(&self) -> impl Iterator<Item = &str> {
+        let fields = match self {
+            OutputType::Named(fields) => fields.as_slice(),
+            _ => &[],
+        };
+        fields.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Value at a dot separated `path` such as `"macd.signal"` or `"bands.1"`.
+    ///
+    /// Names select fields of `Named` outputs and integers select elements of
+    /// `Custom`, `Array` and `Statics` outputs, an empty path returns the output itself.
+    pub fn get_path(&self, path: &str) -> Option<OutputType> {
+        let mut current = self;
+        let mut segments = path.split('.').filter(|segment| !segment.is_empty());
+        while let Some(segment) = segments.next() {
+            current = match current {
+                OutputType::Named(_) => current.field(segment)?,
+                OutputType::Custom(vec) => vec.get(segment.parse::<usize>().ok()?)?,
+                OutputType::Array(vec) => {
+                    let value = *vec.get(segment.parse::<usize>().ok()?)?;
+                    return segments
+                        .next()
+                        .is_none()
+                        .then_some(OutputType::Single(value));
+                }
+                OutputType::Statics(vec) => {
+                    let value = vec.get(segment.parse::<usize>().ok()?)?.clone();
+                    return segments
+                        .next()
+                        .is_none()
+                        .then_some(OutputType::Static(value));
+                }
+                _ => return None,
+            };
         }
+        Some(current.clone())
     }
 
     /// Turn any OutputType into actual Single/Array by pulling from the candle.
+    ///
+    /// `Custom` values are resolved recursively, every candle field becomes a
+    /// `Single` so the `output_shape` of the result is the same as the original.
+    /// `Missing` values stay missing.
     pub fn resolve<C: Candle>(&self, data: &C) -> TaUtilsResult<OutputType> {
         match self {
             OutputType::Single(_) | OutputType::Array(_) => Ok(self.clone()),
@@ -112,27 +262,35 @@ impl OutputType {
             OutputType::High => Ok(OutputType::Single(Number::float(data.high()))),
             OutputType::Low => Ok(OutputType::Single(Number::float(data.low()))),
             OutputType::Volume => Ok(OutputType::Single(Number::float(data.volume()))),
-            OutputType::Custom(vec) => {
-                let mut out = Vec::with_capacity(vec.len());
-                for ot in vec {
-                    match ot.resolve(data)? {
-                        // FIXME: Fix it for when output types support complex shapes
-                        OutputType::Single(v) => out.push(OutputType::Single(v)),
-                        OutputType::Static(s) => out.push(OutputType::Static(s)),
-                        _ => {
-                            return Err(TaUtilsError::IncorrectOutputType {
-                                expected: "Single".into(),
-                                actual: "Array".into(),
-                            });
-                        }
-                    }
-                }
-                Ok(OutputType::Custom(out))
-            }
+            OutputType::Custom(vec) => vec
+                .iter()
+                .map(|ot| ot.resolve(data))
+                .collect::<TaUtilsResult<Vec<OutputType>>>()
+                .map(OutputType::Custom),
             OutputType::Static(_) => Ok(self.clone()),
             OutputType::Statics(_) => Ok(self.clone()),
+            OutputType::Missing => Ok(OutputType::Missing),
+            OutputType::Named(fields) => fields
+                .iter()
+                .map(|(name, ot)| Ok((name.clone(), ot.resolve(data)?)))
+                .collect::<TaUtilsResult<Vec<(String, OutputType)>>>()
+                .map(OutputType::Named),
         }
     }
+
+    /// Equality within `tolerance`, see [`Tolerance::approx_eq`].
+    pub fn approx_eq(&self, other: &OutputType, tolerance: &Tolerance) -> bool {
+        tolerance.approx_eq(self, other)
+    }
+
+    /// Ordering within `tolerance`, see [`Tolerance::approx_cmp`].
+    pub fn approx_cmp(
+        &self,
+        other: &OutputType,
+        tolerance: &Tolerance,
+    ) -> Option<std::cmp::Ordering> {
+        tolerance.approx_cmp(self, other)
+    }
 }
 
 impl OutputShape {
@@ -149,6 +307,18 @@ impl OutputShape {
                 }
                 Ok(self.clone())
             }
+            OutputShape::Named(fields) if !fields.is_empty() => {
+                for (i, (name, shape)) in fields.iter().enumerate() {
+                    // Field names must be unique to be addressable
+                    if fields[..i].iter().any(|(other, _)| other == name) {
+                        return Err(TaUtilsError::from(OutputError::InvalidOutputShape(
+                            self.clone(),
+                        )));
+                    }
+                    shape.validate()?;
+                }
+                Ok(self.clone())
+            }
             shape => Err(TaUtilsError::from(OutputError::InvalidOutputShape(
                 shape.clone(),
             ))),
@@ -180,11 +350,19 @@ impl From<Vec<f64>> for OutputType {
     }
 }
 
+impl<T: Into<OutputType>> From<Option<T>> for OutputType {
+    /// `None` becomes [`OutputType::Missing`], e.g. the output of a [`crate::Warmup`].
+    fn from(value: Option<T>) -> Self {
+        value.map_or(OutputType::Missing, Into::into)
+    }
+}
+
 impl TryFrom<&OutputType> for Number {
     type Error = TaUtilsError;
     fn try_from(value: &OutputType) -> Result<Self, Self::Error> {
         match value {
             OutputType::Single(output) => Ok(*output),
+            OutputType::Missing => Err(OutputError::Missing.into()),
             OutputType::Array(_) => Err(TaUtilsError::IncorrectOutputType {
                 expected: "Number".to_string(),
                 actual: "Vec<Number>".to_string(),
@@ -211,6 +389,7 @@ impl TryFrom<OutputType> for f64 {
     fn try_from(value: OutputType) -> Result<Self, Self::Error> {
         match value {
             OutputType::Single(output) => Ok(output.as_f64()),
+            OutputType::Missing => Err(OutputError::Missing.into()),
             OutputType::Array(_) => Err(TaUtilsError::IncorrectOutputType {
                 expected: "f64".to_string(),
                 actual: "Vec<Number>".to_string(),
@@ -229,6 +408,7 @@ impl TryFrom<&OutputType> for Vec<Number> {
         match value {
             OutputType::Array(output) => Ok(output.iter().map(|n| *n).collect()),
             OutputType::Custom(output) => output.into_iter().map(|o| o.try_into()).collect::<Result<Vec<Number>, TaUtilsError>>(),
+            OutputType::Missing => Err(OutputError::Missing.into()),
             OutputType::Single(_) => Err(TaUtilsError::IncorrectOutputType {
                 expected: "Vec<Number>".to_string(),
                 actual: "Number".to_string(),
@@ -254,6 +434,7 @@ impl TryFrom<OutputType> for Vec<f64> {
     fn try_from(value: OutputType) -> Result<Self, Self::Error> {
         match value {
             OutputType::Array(output) => Ok(output.into_iter().map(|n| n.as_f64()).collect()),
+            OutputType::Missing => Err(OutputError::Missing.into()),
             OutputType::Single(_) => Err(TaUtilsError::IncorrectOutputType {
                 expected: "Vec<f64>".to_string(),
                 actual: "Number".to_string(),
@@ -266,7 +447,8 @@ impl TryFrom<OutputType> for Vec<f64> {
     }
 }
 
-// TODO: Implement PartialEq and PartialOrd for OutputType using std::f64::EPSILON
+// Exact comparison, use `approx_eq` / `approx_cmp` or `Tolerance::wrap` to absorb float noise.
+// `Missing` values fall in the last arm, they are never ordered.
 impl PartialOrd for OutputType {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         match (self, other) {
@@ -375,7 +557,97 @@ impl PartialOrd for OutputType {
                     }
                 }
             }
+            (OutputType::Named(a), OutputType::Named(b)) => {
+                if a.len() != b.len() {
+                    None
+                } else {
+                    let mut equals = Vec::new();
+                    for ((a_name, a_val), (b_name, b_val)) in a.iter().zip(b.iter()) {
+                        if a_name != b_name {
+                            return None;
+                        }
+                        if let Some(ordering) = a_val.partial_cmp(b_val) {
+                            equals.push(ordering);
+                        }
+                    }
+                    if equals.is_empty() || equals.len() != a.len() {
+                        return None;
+                    }
+                    match equals.iter().all(|&o| o == equals[0]) {
+                        true => Some(equals[0]),
+                        false => None,
+                    }
+                }
+            }
             _ => None,
         }
     }
 }
+
+/// Reads `null` as `NaN`, which is how JSON writes non finite numbers.
+fn nan_if_null<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Number, D::Error> {
+    if deserializer.is_human_readable() {
+        Ok(Option::<Number>::deserialize(deserializer)?.unwrap_or(Number::float(f64::NAN)))
+    } else {
+        Number::deserialize(deserializer)
+    }
+}
+
+/// [`nan_if_null`] for every element.
+fn nans_if_null<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Number>, D::Error> {
+    if deserializer.is_human_readable() {
+        Ok(Vec::<Option<Number>>::deserialize(deserializer)?
+            .into_iter()
+            .map(|value| value.unwrap_or(Number::float(f64::NAN)))
+            .collect())
+    } else {
+        Vec::deserialize(deserializer)
+    }
+}
+
+/// Serializes `(name, value)` pairs as a map, keeping their order.
+mod named {
+    use core::fmt;
+    use core::marker::PhantomData;
+
+    use serde::de::{Deserialize, Deserializer, Error, MapAccess, Visitor};
+    use serde::ser::{Serialize, SerializeMap, Serializer};
+
+    pub fn serialize<S: Serializer, T: Serialize>(
+        fields: &[(String, T)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(fields.len()))?;
+        for (name, value) in fields {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, T)>, D::Error> {
+        struct FieldsVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for FieldsVisitor<T> {
+            type Value = Vec<(String, T)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map of named fields")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut fields: Vec<(String, T)> = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some((name, value)) = map.next_entry::<String, T>()? {
+                    if fields.iter().any(|(field, _)| *field == name) {
+                        return Err(A::Error::custom(format!("duplicate field '{name}'")));
+                    }
+                    fields.push((name, value));
+                }
+                Ok(fields)
+            }
+        }
+
+        deserializer.deserialize_map(FieldsVisitor(PhantomData))
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{TaUtilsError, TaUtilsResult};
+
+/// Type of the values a parameter takes.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ParamKind {
+    Integer,
+    Float,
+    Bool,
+    /// One of a fixed set of names.
+    Choice(Vec<String>),
+}
+
+/// Value of a parameter.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParamValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl ParamValue {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ParamValue::Integer(value) => Some(*value as f64),
+            ParamValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Integer value, floats without a fractional part are accepted.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ParamValue::Integer(value) => Some(*value),
+            ParamValue::Float(value) if value.fract() == 0.0 => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ParamValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ParamValue::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for ParamValue {
+    fn from(value: bool) -> Self {
+        ParamValue::Bool(value)
+    }
+}
+
+impl From<i64> for ParamValue {
+    fn from(value: i64) -> Self {
+        ParamValue::Integer(value)
+    }
+}
+
+impl From<usize> for ParamValue {
+    /// Saturates at `i64::MAX`.
+    fn from(value: usize) -> Self {
+        ParamValue::Integer(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<f64> for ParamValue {
+    fn from(value: f64) -> Self {
+        ParamValue::Float(value)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(value: &str) -> Self {
+        ParamValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for ParamValue {
+    fn from(value: String) -> Self {
+        ParamValue::Text(value)
+    }
+}
+
+/// Describes a parameter of an indicator, for user interfaces and optimizers.
+///
+/// `min` and `max` are inclusive bounds checked by [`ParamDescriptor::validate`],
+/// `step` is only a hint of the resolution worth exploring in a grid search.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamDescriptor {
+    pub name: String,
+    pub description: Option<String>,
+    pub kind: ParamKind,
+    pub default: ParamValue,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub step: Option<f64>,
+}
+
+impl ParamDescriptor {
+    fn new(name: &str, kind: ParamKind, default: ParamValue) -> Self {
+        Self {
+            name: name.to_string(),
+            description: None,
+            kind,
+            default,
+            min: None,
+            max: None,
+            step: None,
+        }
+    }
+
+    pub fn integer(name: &str, default: i64) -> Self {
+        Self::new(name, ParamKind::Integer, ParamValue::Integer(default))
+    }
+
+    pub fn float(name: &str, default: f64) -> Self {
+        Self::new(name, ParamKind::Float, ParamValue::Float(default))
+    }
+
+    pub fn bool(name: &str, default: bool) -> Self {
+        Self::new(name, ParamKind::Bool, ParamValue::Bool(default))
+    }
+
+    pub fn choice(name: &str, options: &[&str], default: &str) -> Self {
+        let options = options.iter().map(|option| option.to_string()).collect();
+        Self::new(name, ParamKind::Choice(options), default.into())
+    }
+
+    /// The window length parameter shared by the rolling indicators.
+    pub fn period(default: usize) -> Self {
+        Self::integer("period", i64::try_from(default).unwrap_or(i64::MAX))
+            .set_description("Number of values in the window")
+            .set_min(1.0)
+            .set_step(1.0)
+    }
+
+    pub fn set_description(mut self, val: &str) -> Self {
+        self.description = Some(val.to_string());
+        self
+    }
+
+    pub fn set_min(mut self, val: f64) -> Self {
+        self.min = Some(val);
+        self
+    }
+
+    pub fn set_max(mut self, val: f64) -> Self {
+        self.max = Some(val);
+        self
+    }
+
+    pub fn set_step(mut self, val: f64) -> Self {
+        self.step = Some(val);
+        self
+    }
+
+    /// Checks `value` against the type and bounds of the parameter, returning it
+    /// converted to the type of the parameter (e.g. `2` to `2.0` for a float).
+    pub fn validate(&self, value: &ParamValue) -> TaUtilsResult<ParamValue> {
+        let invalid = |expected: &str| {
+            TaUtilsError::InvalidParameter(format!(
+                "Parameter '{}' must be {expected}, got {value:?}",
+                self.name
+            ))
+        };
+        let value = match &self.kind {
+            ParamKind::Integer => {
+                ParamValue::Integer(value.as_i64().ok_or_else(|| invalid("an integer"))?)
+            }
+            ParamKind::Float => ParamValue::Float(
+                value
+                    .as_f64()
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| invalid("a finite number"))?,
+            ),
+            ParamKind::Bool => {
+                ParamValue::Bool(value.as_bool().ok_or_else(|| invalid("a boolean"))?)
+            }
+            ParamKind::Choice(options) => {
+                let choice = value
+                    .as_str()
+                    .filter(|choice| options.iter().any(|option| option == choice))
+                    .ok_or_else(|| invalid(&format!("one of {}", options.join(", "))))?;
+                ParamValue::Text(choice.to_string())
+            }
+        };
+        if let Some(number) = value.as_f64()
+            && (self.min.is_some_and(|min| number < min)
+                || self.max.is_some_and(|max| number > max))
+        {
+            return Err(invalid(&format!(
+                "in [{}, {}]",
+                self.min.unwrap_or(f64::NEG_INFINITY),
+                self.max.unwrap_or(f64::INFINITY)
+            )));
+        }
+        Ok(value)
+    }
+}
+
+/// Parameter values by name.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ParamMap(BTreeMap<String, ParamValue>);
+
+impl ParamMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(mut self, name: &str, value: impl Into<ParamValue>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, value: impl Into<ParamValue>) -> Option<ParamValue> {
+        self.0.insert(name.to_string(), value.into())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ParamValue> {
+        self.0.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ParamValue)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Validates every value against `descriptors` and adds the default value of
+    /// the missing parameters. Unknown parameters are rejected.
+    pub fn resolve(&self, descriptors: &[ParamDescriptor]) -> TaUtilsResult<ParamMap> {
+        if let Some(name) = self.0.keys().find(|name| {
+            !descriptors
+                .iter()
+                .any(|descriptor| &descriptor.name == *name)
+        }) {
+            return Err(TaUtilsError::InvalidParameter(format!(
+                "Unknown parameter '{name}'"
+            )));
+        }
+        let mut resolved = ParamMap::new();
+        for descriptor in descriptors {
+            let value = self.get(&descriptor.name).unwrap_or(&descriptor.default);
+            resolved.insert(&descriptor.name, descriptor.validate(value)?);
+        }
+        Ok(resolved)
+    }
+
+    fn require(&self, name: &str) -> TaUtilsResult<&ParamValue> {
+        self.get(name)
+            .ok_or_else(|| TaUtilsError::InvalidParameter(format!("Missing parameter '{name}'")))
+    }
+
+    fn typed<T>(&self, name: &str, value: Option<T>, expected: &str) -> TaUtilsResult<T> {
+        value.ok_or_else(|| {
+            TaUtilsError::InvalidParameter(format!("Parameter '{name}' must be {expected}"))
+        })
+    }
+
+    pub fn f64(&self, name: &str) -> TaUtilsResult<f64> {
+        self.typed(name, self.require(name)?.as_f64(), "a number")
+    }
+
+    pub fn i64(&self, name: &str) -> TaUtilsResult<i64> {
+        self.typed(name, self.require(name)?.as_i64(), "an integer")
+    }
+
+    pub fn usize(&self, name: &str) -> TaUtilsResult<usize> {
+        let value = self
+            .require(name)?
+            .as_i64()
+            .and_then(|value| usize::try_from(value).ok());
+        self.typed(name, value, "a non negative integer")
+    }
+
+    pub fn bool(&self, name: &str) -> TaUtilsResult<bool> {
+        self.typed(name, self.require(name)?.as_bool(), "a boolean")
+    }
+
+    pub fn str(&self, name: &str) -> TaUtilsResult<&str> {
+        self.typed(name, self.require(name)?.as_str(), "a string")
+    }
+}
+
+impl FromIterator<(String, ParamValue)> for ParamMap {
+    fn from_iter<I: IntoIterator<Item = (String, ParamValue)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Introspection and construction of an indicator from its parameters.
+pub trait Parameters {
+    /// Describes every parameter, in the order they should be presented.
+    fn descriptors() -> Vec<ParamDescriptor>
+    where
+        Self: Sized;
+
+    /// Current value of every parameter.
+    fn params(&self) -> ParamMap;
+
+    /// Builds the indicator, missing parameters take their default value.
+    fn from_params(params: &ParamMap) -> TaUtilsResult<Self>
+    where
+        Self: Sized;
+
+    /// JSON Schema of the [`ParamMap`] accepted by [`Parameters::from_params`].
+    #[cfg(feature = "schemars")]
+    fn params_schema() -> schemars::Schema
+    where
+        Self: Sized,
+    {
+        params_schema(&Self::descriptors())
+    }
+}
+
+/// Implements [`Parameters`] for an indicator built by `new(period)`, with the
+/// default period advertised by its descriptor.
+macro_rules! period_params {
+    ($ty:ty, $default:expr) => {
+        impl $crate::params::Parameters for $ty {
+            fn descriptors() -> Vec<$crate::params::ParamDescriptor> {
+                vec![$crate::params::ParamDescriptor::period($default)]
+            }
+
+            fn params(&self) -> $crate::params::ParamMap {
+                $crate::params::ParamMap::new().set("period", $crate::Period::period(self))
+            }
+
+            fn from_params(params: &$crate::params::ParamMap) -> $crate::TaUtilsResult<Self> {
+                let params = params.resolve(&Self::descriptors())?;
+                Self::new(params.usize("period")?)
+            }
+        }
+    };
+}
+
+pub(crate) use period_params;
+
+/// Builds the JSON Schema of a parameter map, `step` is exposed as the `x-step` keyword.
+#[cfg(feature = "schemars")]
+pub fn params_schema(descriptors: &[ParamDescriptor]) -> schemars::Schema {
+    let mut properties = schemars::json_schema!({});
+    for descriptor in descriptors {
+        let mut property = schemars::json_schema!({
+            "default": descriptor.default,
+        });
+        match &descriptor.kind {
+            ParamKind::Integer => property.insert("type".into(), "integer".into()),
+            ParamKind::Float => property.insert("type".into(), "number".into()),
+            ParamKind::Bool => property.insert("type".into(), "boolean".into()),
+            ParamKind::Choice(options) => property.insert("enum".into(), options.clone().into()),
+        };
+        if let Some(description) = &descriptor.description {
+            property.insert("description".into(), description.clone().into());
+        }
+        if let Some(min) = descriptor.min {
+            property.insert("minimum".into(), min.into());
+        }
+        if let Some(max) = descriptor.max {
+            property.insert("maximum".into(), max.into());
+        }
+        if let Some(step) = descriptor.step {
+            property.insert("x-step".into(), step.into());
+        }
+        properties.insert(descriptor.name.clone(), property.to_value());
+    }
+    schemars::json_schema!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
@@ -0,0 +1,583 @@
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::params::{ParamDescriptor, ParamMap, Parameters};
+use crate::{
+    Bar, Candle, IndicatorTrait, Next, OutputShape, OutputType, Period, Queue, Readiness, Reset,
+    Statics, TaUtilsError, TaUtilsResult,
+};
+
+/// Body and wick thresholds used by the pattern detectors, expressed as ratios.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PatternConfig {
+    /// Maximum body / range ratio of a doji.
+    pub doji_body_ratio: f64,
+    /// Maximum body / range ratio of a small body (hammer, star middle candle).
+    pub small_body_ratio: f64,
+    /// Minimum body / range ratio of a long body (harami mother, star first candle, soldiers).
+    pub long_body_ratio: f64,
+    /// Minimum wick / body ratio of a long wick (hammer, shooting star).
+    pub long_wick_ratio: f64,
+    /// Maximum wick / range ratio of a short wick (hammer, shooting star).
+    pub short_wick_ratio: f64,
+}
+
+impl Default for PatternConfig {
+    fn default() -> Self {
+        Self {
+            doji_body_ratio: 0.1,
+            small_body_ratio: 0.3,
+            long_body_ratio: 0.6,
+            long_wick_ratio: 2.0,
+            short_wick_ratio: 0.1,
+        }
+    }
+}
+
+impl PatternConfig {
+    pub fn validate(&self) -> TaUtilsResult<()> {
+        let ratios = [
+            ("doji_body_ratio", self.doji_body_ratio),
+            ("small_body_ratio", self.small_body_ratio),
+            ("long_body_ratio", self.long_body_ratio),
+            ("long_wick_ratio", self.long_wick_ratio),
+            ("short_wick_ratio", self.short_wick_ratio),
+        ];
+        for (name, ratio) in ratios {
+            if !ratio.is_finite() || ratio < 0.0 {
+                return Err(TaUtilsError::InvalidParameter(format!(
+                    "{name} must be a non negative number, got {ratio}"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PatternConfig {
+    /// Reads the ratios from parameters already resolved against [`PatternConfig::descriptors`].
+    fn read(params: &ParamMap) -> TaUtilsResult<Self> {
+        let config = Self {
+            doji_body_ratio: params.f64("doji_body_ratio")?,
+            small_body_ratio: params.f64("small_body_ratio")?,
+            long_body_ratio: params.f64("long_body_ratio")?,
+            long_wick_ratio: params.f64("long_wick_ratio")?,
+            short_wick_ratio: params.f64("short_wick_ratio")?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Parameters for PatternConfig {
+    fn descriptors() -> Vec<ParamDescriptor> {
+        let default = Self::default();
+        let ratio = |name: &str, value: f64, description: &str| {
+            ParamDescriptor::float(name, value)
+                .set_description(description)
+                .set_min(0.0)
+                .set_step(0.05)
+        };
+        vec![
+            ratio(
+                "doji_body_ratio",
+                default.doji_body_ratio,
+                "Maximum body / range ratio of a doji",
+            ),
+            ratio(
+                "small_body_ratio",
+                default.small_body_ratio,
+                "Maximum body / range ratio of a small body",
+            ),
+            ratio(
+                "long_body_ratio",
+                default.long_body_ratio,
+                "Minimum body / range ratio of a long body",
+            ),
+            ratio(
+                "long_wick_ratio",
+                default.long_wick_ratio,
+                "Minimum wick / body ratio of a long wick",
+            ),
+            ratio(
+                "short_wick_ratio",
+                default.short_wick_ratio,
+                "Maximum wick / range ratio of a short wick",
+            ),
+        ]
+    }
+
+    fn params(&self) -> ParamMap {
+        ParamMap::new()
+            .set("doji_body_ratio", self.doji_body_ratio)
+            .set("small_body_ratio", self.small_body_ratio)
+            .set("long_body_ratio", self.long_body_ratio)
+            .set("long_wick_ratio", self.long_wick_ratio)
+            .set("short_wick_ratio", self.short_wick_ratio)
+    }
+
+    fn from_params(params: &ParamMap) -> TaUtilsResult<Self> {
+        Self::read(&params.resolve(&Self::descriptors())?)
+    }
+}
+
+/// Direction of a pattern which exists in a bullish and a bearish form.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Bullish,
+    Bearish,
+}
+
+impl Direction {
+    fn descriptor() -> ParamDescriptor {
+        ParamDescriptor::choice("direction", &["Bullish", "Bearish"], "Bullish")
+            .set_description("Form of the pattern to detect")
+    }
+
+    fn from_params(params: &ParamMap) -> TaUtilsResult<Self> {
+        match params.str("direction")? {
+            "Bullish" => Ok(Direction::Bullish),
+            "Bearish" => Ok(Direction::Bearish),
+            other => Err(TaUtilsError::InvalidParameter(format!(
+                "Unknown direction '{other}'"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Bullish => write!(f, "Bullish"),
+            Direction::Bearish => write!(f, "Bearish"),
+        }
+    }
+}
+
+/// Implements the window handling shared by every detector. The detector must have
+/// `window: Queue<Bar>`, `seen: usize` and `config: PatternConfig` fields and a
+/// `detect` method called with the full window, oldest candle first.
+macro_rules! pattern {
+    (@common $name:ident, $len:expr) => {
+        impl $name {
+            /// Number of candles forming the pattern.
+            pub const LEN: usize = $len;
+
+            pub fn config(&self) -> &PatternConfig {
+                &self.config
+            }
+        }
+
+        impl Next<&dyn Candle> for $name {
+            type Output = OutputType;
+
+            fn next(&mut self, input: &dyn Candle) -> TaUtilsResult<Self::Output> {
+                self.window.push(input.to_bar());
+                self.seen += 1;
+                let found = self.window.is_full() && self.detect();
+                Ok(OutputType::Static(if found {
+                    Statics::True
+                } else {
+                    Statics::False
+                }))
+            }
+        }
+
+        impl Period for $name {
+            fn period(&self) -> usize {
+                Self::LEN
+            }
+        }
+
+        impl Reset for $name {
+            fn reset(&mut self) {
+                self.window.reset();
+                self.seen = 0;
+            }
+        }
+
+        impl Readiness for $name {
+            fn bars_seen(&self) -> usize {
+                self.seen
+            }
+        }
+
+        impl IndicatorTrait for $name {
+            fn output_shape(&self) -> OutputShape {
+                OutputShape::Shape(1)
+            }
+
+            fn param_descriptors(&self) -> Vec<ParamDescriptor> {
+                <Self as Parameters>::descriptors()
+            }
+
+            fn param_values(&self) -> ParamMap {
+                self.params()
+            }
+
+            fn readiness(&self) -> Option<&dyn Readiness> {
+                Some(self)
+            }
+        }
+    };
+    ($name:ident, $len:expr) => {
+        pattern!(@common $name, $len);
+
+        impl Parameters for $name {
+            fn descriptors() -> Vec<ParamDescriptor> {
+                PatternConfig::descriptors()
+            }
+
+            fn params(&self) -> ParamMap {
+                self.config.params()
+            }
+
+            fn from_params(params: &ParamMap) -> TaUtilsResult<Self> {
+                Self::new(PatternConfig::from_params(params)?)
+            }
+        }
+    };
+    ($name:ident, $len:expr, direction) => {
+        pattern!(@common $name, $len);
+
+        impl Parameters for $name {
+            fn descriptors() -> Vec<ParamDescriptor> {
+                let mut descriptors = vec![Direction::descriptor()];
+                descriptors.extend(PatternConfig::descriptors());
+                descriptors
+            }
+
+            fn params(&self) -> ParamMap {
+                self.config
+                    .params()
+                    .set("direction", self.direction.to_string())
+            }
+
+            fn from_params(params: &ParamMap) -> TaUtilsResult<Self> {
+                let params = params.resolve(&Self::descriptors())?;
+                Self::new(
+                    Direction::from_params(&params)?,
+                    PatternConfig::read(&params)?,
+                )
+            }
+        }
+    };
+}
+
+/// Single candle whose body is negligible compared to its range.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Doji {
+    config: PatternConfig,
+    window: Queue<Bar>,
+    seen: usize,
+}
+
+impl Doji {
+    pub fn new(config: PatternConfig) -> TaUtilsResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            window: Queue::new(Self::LEN)?,
+            seen: 0,
+        })
+    }
+
+    fn detect(&self) -> bool {
+        let c = &self.window[0];
+        c.range() > 0.0 && c.is_doji(self.config.doji_body_ratio)
+    }
+}
+
+impl fmt::Display for Doji {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Doji")
+    }
+}
+
+pattern!(Doji, 1);
+
+/// Single candle with a small body at the top of the range and a long lower wick.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hammer {
+    config: PatternConfig,
+    window: Queue<Bar>,
+    seen: usize,
+}
+
+impl Hammer {
+    pub fn new(config: PatternConfig) -> TaUtilsResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            window: Queue::new(Self::LEN)?,
+            seen: 0,
+        })
+    }
+
+    fn detect(&self) -> bool {
+        let c = &self.window[0];
+        let range = c.range();
+        range > 0.0
+            && c.body() <= self.config.small_body_ratio * range
+            && c.lower_wick() >= self.config.long_wick_ratio * c.body()
+            && c.lower_wick() > c.upper_wick()
+            && c.upper_wick() <= self.config.short_wick_ratio * range
+    }
+}
+
+impl fmt::Display for Hammer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Hammer")
+    }
+}
+
+pattern!(Hammer, 1);
+
+/// Single candle with a small body at the bottom of the range and a long upper wick.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShootingStar {
+    config: PatternConfig,
+    window: Queue<Bar>,
+    seen: usize,
+}
+
+impl ShootingStar {
+    pub fn new(config: PatternConfig) -> TaUtilsResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            config,
+            window: Queue::new(Self::LEN)?,
+            seen: 0,
+        })
+    }
+
+    fn detect(&self) -> bool {
+        let c = &self.window[0];
+        let range = c.range();
+        range > 0.0
+            && c.body() <= self.config.small_body_ratio * range
+            && c.upper_wick() >= self.config.long_wick_ratio * c.body()
+            && c.upper_wick() > c.lower_wick()
+            && c.lower_wick() <= self.config.short_wick_ratio * range
+    }
+}
+
+impl fmt::Display for ShootingStar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ShootingStar")
+    }
+}
+
+pattern!(ShootingStar, 1);
+
+/// Two candles where the body of the second one, in the pattern direction,
+/// engulfs the opposite colored body of the first one.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Engulfing {
+    direction: Direction,
+    config: PatternConfig,
+    window: Queue<Bar>,
+    seen: usize,
+}
+
+impl Engulfing {
+    pub fn new(direction: Direction, config: PatternConfig) -> TaUtilsResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            direction,
+            config,
+            window: Queue::new(Self::LEN)?,
+            seen: 0,
+        })
+    }
+
+    fn detect(&self) -> bool {
+        let (p, c) = (&self.window[0], &self.window[1]);
+        let engulfs = c.body() > p.body()
+            && c.open.min(c.close) <= p.open.min(p.close)
+            && c.open.max(c.close) >= p.open.max(p.close);
+        engulfs
+            && match self.direction {
+                Direction::Bullish => p.is_bearish() && c.is_bullish(),
+                Direction::Bearish => p.is_bullish() && c.is_bearish(),
+            }
+    }
+}
+
+impl fmt::Display for Engulfing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Engulfing({})", self.direction)
+    }
+}
+
+pattern!(Engulfing, 2, direction);
+
+/// Two candles where a long body is followed by a smaller opposite colored body,
+/// in the pattern direction, contained within it.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Harami {
+    direction: Direction,
+    config: PatternConfig,
+    window: Queue<Bar>,
+    seen: usize,
+}
+
+impl Harami {
+    pub fn new(direction: Direction, config: PatternConfig) -> TaUtilsResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            direction,
+            config,
+            window: Queue::new(Self::LEN)?,
+            seen: 0,
+        })
+    }
+
+    fn detect(&self) -> bool {
+        let (p, c) = (&self.window[0], &self.window[1]);
+        let contained = p.body() >= self.config.long_body_ratio * p.range()
+            && c.body() < p.body()
+            && c.open.min(c.close) >= p.open.min(p.close)
+            && c.open.max(c.close) <= p.open.max(p.close);
+        contained
+            && match self.direction {
+                Direction::Bullish => p.is_bearish() && c.is_bullish(),
+                Direction::Bearish => p.is_bullish() && c.is_bearish(),
+            }
+    }
+}
+
+impl fmt::Display for Harami {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Harami({})", self.direction)
+    }
+}
+
+pattern!(Harami, 2, direction);
+
+/// Morning star (bullish) or evening star (bearish): a long body, a small body
+/// beyond its close, then a body in the opposite direction closing past the
+/// middle of the first one.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Star {
+    direction: Direction,
+    config: PatternConfig,
+    window: Queue<Bar>,
+    seen: usize,
+}
+
+impl Star {
+    pub fn new(direction: Direction, config: PatternConfig) -> TaUtilsResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            direction,
+            config,
+            window: Queue::new(Self::LEN)?,
+            seen: 0,
+        })
+    }
+
+    fn detect(&self) -> bool {
+        let (first, star, last) = (&self.window[0], &self.window[1], &self.window[2]);
+        let shapes = first.body() >= self.config.long_body_ratio * first.range()
+            && star.body() <= self.config.small_body_ratio * star.range();
+        let middle = (first.open + first.close) / 2.0;
+        shapes
+            && match self.direction {
+                Direction::Bullish => {
+                    first.is_bearish()
+                        && star.open.max(star.close) <= first.close
+                        && last.is_bullish()
+                        && last.close > middle
+                }
+                Direction::Bearish => {
+                    first.is_bullish()
+                        && star.open.min(star.close) >= first.close
+                        && last.is_bearish()
+                        && last.close < middle
+                }
+            }
+    }
+}
+
+impl fmt::Display for Star {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.direction {
+            Direction::Bullish => write!(f, "MorningStar"),
+            Direction::Bearish => write!(f, "EveningStar"),
+        }
+    }
+}
+
+pattern!(Star, 3, direction);
+
+/// Three white soldiers (bullish) or three black crows (bearish): three long
+/// bodies in the same direction, each opening within the previous body and
+/// closing beyond the previous close.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreeSoldiers {
+    direction: Direction,
+    config: PatternConfig,
+    window: Queue<Bar>,
+    seen: usize,
+}
+
+impl ThreeSoldiers {
+    pub fn new(direction: Direction, config: PatternConfig) -> TaUtilsResult<Self> {
+        config.validate()?;
+        Ok(Self {
+            direction,
+            config,
+            window: Queue::new(Self::LEN)?,
+            seen: 0,
+        })
+    }
+
+    fn detect(&self) -> bool {
+        let long = |c: &Bar| c.body() >= self.config.long_body_ratio * c.range();
+        if !self.window.iter().all(long) {
+            return false;
+        }
+        let candles = [&self.window[0], &self.window[1], &self.window[2]];
+        candles.windows(2).all(|pair| {
+            let (p, c) = (pair[0], pair[1]);
+            match self.direction {
+                Direction::Bullish => {
+                    p.is_bullish()
+                        && c.is_bullish()
+                        && c.open >= p.open
+                        && c.open <= p.close
+                        && c.close > p.close
+                }
+                Direction::Bearish => {
+                    p.is_bearish()
+                        && c.is_bearish()
+                        && c.open <= p.open
+                        && c.open >= p.close
+                        && c.close < p.close
+                }
+            }
+        })
+    }
+}
+
+impl fmt::Display for ThreeSoldiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.direction {
+            Direction::Bullish => write!(f, "ThreeWhiteSoldiers"),
+            Direction::Bearish => write!(f, "ThreeBlackCrows"),
+        }
+    }
+}
+
+pattern!(ThreeSoldiers, 3, direction);
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Bar, Candle, Next, Reset, Snapshot, TaUtilsError, TaUtilsResult, Timeframe};
+
+/// Aggregates a stream of candles or ticks into bars of a larger [`Timeframe`].
+///
+/// Each bar takes the first open, highest high, lowest low, last close and
+/// price, and the summed volume of the inputs falling in its bucket. A bucket
+/// is emitted as soon as an input shows it is closed: either an input whose own
+/// timeframe ends on the bucket boundary, or the first input of a later bucket.
+/// Empty buckets caused by gaps in the data are skipped, and the partial last
+/// bucket can be retrieved with [`Resampler::flush`].
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Resampler {
+    timeframe: Timeframe,
+    current: Option<Bar>,
+}
+
+impl Resampler {
+    /// Builds a resampler, any [`Timeframe`] is valid as zero length ones cannot be built.
+    pub fn new(timeframe: Timeframe) -> Self {
+        Self {
+            timeframe,
+            current: None,
+        }
+    }
+
+    pub fn timeframe(&self) -> Timeframe {
+        self.timeframe
+    }
+
+    /// The bar of the bucket currently being built, if any.
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+
+    /// Returns the bucket currently being built, even if it is not complete.
+    pub fn flush(&mut self) -> Option<Bar> {
+        self.current.take()
+    }
+
+    /// Adds a candle opened at `timestamp` (milliseconds since the Unix epoch),
+    /// returning the bar of the previous bucket if this candle completes it.
+    pub fn push(&mut self, timestamp: i64, candle: &dyn Candle) -> TaUtilsResult<Option<Bar>> {
+        let start = self.timeframe.align(timestamp);
+        let end = start
+            .checked_add(self.timeframe.as_millis())
+            .ok_or_else(|| {
+                TaUtilsError::InvalidParameter(format!(
+                    "Candle at {timestamp} is past the last {} bucket",
+                    self.timeframe
+                ))
+            })?;
+
+        let mut completed = None;
+        match self.current.as_mut() {
+            Some(bar) if bar.open_time == Some(start) => {
+                bar.high = bar.high.max(candle.high());
+                bar.low = bar.low.min(candle.low());
+                bar.close = candle.close();
+                bar.price = candle.price();
+                bar.volume = add_volume(bar.volume, candle.volume());
+            }
+            Some(bar) if bar.open_time > Some(start) => {
+                return Err(TaUtilsError::Unallowed(format!(
+                    "Candle at {timestamp} is older than the current {} bucket starting at {}",
+                    self.timeframe,
+                    bar.open_time.unwrap_or_default()
+                )));
+            }
+            _ => {
+                completed = self.current.replace(
+                    Bar::new()
+                        .set_open(candle.open())
+                        .set_high(candle.high())
+                        .set_low(candle.low())
+                        .set_close(candle.close())
+                        .set_price(candle.price())
+                        .set_volume(add_volume(f64::NAN, candle.volume()))
+                        .set_open_time(start)
+                        .set_close_time(end)
+                        .set_timeframe(self.timeframe),
+                );
+            }
+        }
+
+        // Close the bucket right away if the candle is known to end on its boundary,
+        // unless a previous bucket is already being emitted by this call.
+        let candle_end = candle
+            .timeframe()
+            .map(|timeframe| timestamp.saturating_add(timeframe.as_millis()));
+        if completed.is_none() && candle_end.is_some_and(|candle_end| candle_end >= end) {
+            completed = self.current.take();
+        }
+        Ok(completed)
+    }
+}
+
+/// Sums volumes ignoring missing (`NaN`) ones, the result is `NaN` only if every volume is missing.
+pub(crate) fn add_volume(total: f64, volume: f64) -> f64 {
+    match (total.is_nan(), volume.is_nan()) {
+        (_, true) => total,
+        (true, false) => volume,
+        (false, false) => total + volume,
+    }
+}
+
+impl Next<&dyn Candle> for Resampler {
+    type Output = Option<Bar>;
+
+    fn next(&mut self, input: &dyn Candle) -> TaUtilsResult<Self::Output> {
+        let timestamp = input.timestamp().ok_or_else(|| {
+            TaUtilsError::InvalidParameter("Cannot resample a candle without timestamp".to_string())
+        })?;
+        self.push(timestamp, input)
+    }
+}
+
+/// Timestamped input, used to build bars from candles that carry no time such as `MarketData::Float` ticks.
+impl Next<(i64, &dyn Candle)> for Resampler {
+    type Output = Option<Bar>;
+
+    fn next(&mut self, (timestamp, input): (i64, &dyn Candle)) -> TaUtilsResult<Self::Output> {
+        self.push(timestamp, input)
+    }
+}
+
+impl Snapshot for Resampler {
+    type State = Self;
+
+    fn snapshot(&self) -> Self::State {
+        self.clone()
+    }
+
+    fn restore(&mut self, state: Self::State) -> TaUtilsResult<()> {
+        if self.timeframe != state.timeframe {
+            return Err(TaUtilsError::InvalidParameter(format!(
+                "Cannot restore a {} resampler state into a {} resampler",
+                state.timeframe, self.timeframe
+            )));
+        }
+        *self = state;
+        Ok(())
+    }
+
+    fn from_snapshot(state: Self::State) -> TaUtilsResult<Self> {
+        Ok(state)
+    }
+}
+
+impl Reset for Resampler {
+    fn reset(&mut self) {
+        self.current = None;
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Bar, BarError, Candle, Next, Period, Reset, TaUtilsError, TaUtilsResult};
+
+/// What a [`Sanitizer`] does with a bar failing [`Bar::validate`].
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SanitizePolicy {
+    /// Return the validation error without feeding the bar to the inner consumer.
+    #[default]
+    Reject,
+    /// Repair the bar: swap high and low when inverted, clamp open, close and price
+    /// into the high-low range and clamp negative volume to `0`.
+    /// Bars with non finite prices cannot be repaired and are rejected.
+    Clamp,
+    /// Replace the bar with a flat bar at the close of the last valid bar, with no volume.
+    /// Rejected if no valid bar was seen yet.
+    ForwardFill,
+}
+
+/// Validates every candle before passing it to the wrapped `Next<&dyn Candle>` consumer,
+/// invalid ones are handled according to its [`SanitizePolicy`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sanitizer<N> {
+    inner: N,
+    policy: SanitizePolicy,
+    last_valid: Option<Bar>,
+}
+
+impl<N> Sanitizer<N> {
+    pub fn new(inner: N, policy: SanitizePolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            last_valid: None,
+        }
+    }
+
+    pub fn policy(&self) -> SanitizePolicy {
+        self.policy
+    }
+
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut N {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> N {
+        self.inner
+    }
+
+    /// Validates the candle, returning the bar to use in its place according to the policy.
+    pub fn sanitize(&mut self, input: &dyn Candle) -> TaUtilsResult<Bar> {
+        let bar = input.to_bar();
+        let err = match bar.validate() {
+            Ok(()) => {
+                self.last_valid = Some(bar.clone());
+                return Ok(bar);
+            }
+            Err(err) => err,
+        };
+        match self.policy {
+            SanitizePolicy::Reject => Err(err),
+            SanitizePolicy::Clamp => {
+                if matches!(err, TaUtilsError::Bar(BarError::NonFinite { .. })) {
+                    return Err(err);
+                }
+                let repaired = clamp(bar);
+                repaired.validate()?;
+                self.last_valid = Some(repaired.clone());
+                Ok(repaired)
+            }
+            SanitizePolicy::ForwardFill => {
+                let Some(previous) = &self.last_valid else {
+                    return Err(err);
+                };
+                let close = previous.close;
+                Ok(Bar {
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    price: close,
+                    volume: 0.0,
+                    ..bar
+                })
+            }
+        }
+    }
+}
+
+fn clamp(mut bar: Bar) -> Bar {
+    if bar.high < bar.low {
+        std::mem::swap(&mut bar.high, &mut bar.low);
+    }
+    bar.open = bar.open.clamp(bar.low, bar.high);
+    bar.close = bar.close.clamp(bar.low, bar.high);
+    bar.price = bar.price.clamp(bar.low, bar.high);
+    if bar.volume < 0.0 {
+        bar.volume = 0.0;
+    }
+    bar
+}
+
+impl<'a, N, O> Next<&'a dyn Candle> for Sanitizer<N>
+where
+    N: for<'b> Next<&'b dyn Candle, Output = O>,
+{
+    type Output = O;
+
+    fn next(&mut self, input: &'a dyn Candle) -> TaUtilsResult<Self::Output> {
+        let bar = self.sanitize(input)?;
+        self.inner.next(&bar)
+    }
+}
+
+impl<N: Period> Period for Sanitizer<N> {
+    fn period(&self) -> usize {
+        self.inner.period()
+    }
+}
+
+impl<N: Reset> Reset for Sanitizer<N> {
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.last_valid = None;
+    }
+}
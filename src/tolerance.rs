@@ -0,0 +1,260 @@
+use core::cmp::Ordering;
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OutputType, Statics};
+use chipa_types::Number;
+
+/// How `NaN` values compare under a [`Tolerance`].
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NanPolicy {
+    /// IEEE 754 semantics, `NaN` is neither equal nor ordered to anything, itself included.
+    #[default]
+    Unordered,
+    /// `NaN` equals `NaN`, it stays unordered relative to every number.
+    Equal,
+}
+
+/// Tolerance used to compare floating point outputs.
+///
+/// Two finite numbers are equal when they are within `absolute` of each other,
+/// within `relative` times the largest magnitude, or at most `ulps` representable
+/// values apart. Infinities only equal themselves.
+///
+/// The default accepts `f64::EPSILON` of absolute error and 4 ULPs, enough to
+/// absorb the rounding noise of the usual indicator arithmetic, use
+/// [`Tolerance::EXACT`] to get the behaviour of `==` back.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tolerance {
+    pub absolute: f64,
+    pub relative: f64,
+    pub ulps: u64,
+    pub nan: NanPolicy,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            absolute: f64::EPSILON,
+            relative: 0.0,
+            ulps: 4,
+            nan: NanPolicy::Unordered,
+        }
+    }
+}
+
+impl Tolerance {
+    /// No tolerance at all, numbers compare like `f64`.
+    pub const EXACT: Self = Self {
+        absolute: 0.0,
+        relative: 0.0,
+        ulps: 0,
+        nan: NanPolicy::Unordered,
+    };
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_absolute(mut self, val: f64) -> Self {
+        self.absolute = val;
+        self
+    }
+
+    pub fn set_relative(mut self, val: f64) -> Self {
+        self.relative = val;
+        self
+    }
+
+    pub fn set_ulps(mut self, val: u64) -> Self {
+        self.ulps = val;
+        self
+    }
+
+    pub fn set_nan(mut self, val: NanPolicy) -> Self {
+        self.nan = val;
+        self
+    }
+
+    pub fn eq_f64(&self, a: f64, b: f64) -> bool {
+        if a == b {
+            return true;
+        }
+        if a.is_nan() || b.is_nan() {
+            return self.nan == NanPolicy::Equal && a.is_nan() && b.is_nan();
+        }
+        if a.is_infinite() || b.is_infinite() {
+            return false;
+        }
+        let diff = (a - b).abs();
+        diff <= self.absolute
+            || diff <= self.relative * a.abs().max(b.abs())
+            || ulps_between(a, b) <= self.ulps
+    }
+
+    /// Orders two numbers, returning `Equal` whenever [`Tolerance::eq_f64`] holds.
+    pub fn cmp_f64(&self, a: f64, b: f64) -> Option<Ordering> {
+        if self.eq_f64(a, b) {
+            Some(Ordering::Equal)
+        } else {
+            a.partial_cmp(&b)
+        }
+    }
+
+    fn eq_number(&self, a: &Number, b: &Number) -> bool {
+        self.eq_f64(a.as_f64(), b.as_f64())
+    }
+
+    fn cmp_number(&self, a: &Number, b: &Number) -> Option<Ordering> {
+        self.cmp_f64(a.as_f64(), b.as_f64())
+    }
+
+    /// Equality of two outputs with this tolerance.
+    ///
+    /// Outputs are equal when they have the same variant and equal elements, and
+    /// the same field names for `Named` outputs. An `Array` also equals a `Custom`
+    /// of as many equal `Single` values. Candle fields (`Open`, `Close`, ...) only
+    /// equal themselves, [`OutputType::resolve`] them first to compare their values.
+    pub fn approx_eq(&self, a: &OutputType, b: &OutputType) -> bool {
+        match (a, b) {
+            (OutputType::Single(a), OutputType::Single(b)) => self.eq_number(a, b),
+            (OutputType::Array(a), OutputType::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| self.eq_number(a, b))
+            }
+            (OutputType::Custom(a), OutputType::Custom(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| self.approx_eq(a, b))
+            }
+            (OutputType::Array(array), OutputType::Custom(custom))
+            | (OutputType::Custom(custom), OutputType::Array(array)) => {
+                array.len() == custom.len()
+                    && array
+                        .iter()
+                        .zip(custom)
+                        .all(|(a, b)| self.approx_eq(&OutputType::Single(*a), b))
+            }
+            (OutputType::Named(a), OutputType::Named(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|((a_name, a), (b_name, b))| a_name == b_name && self.approx_eq(a, b))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// Ordering of two outputs with this tolerance.
+    ///
+    /// Follows the rules of the `PartialOrd` implementation of [`OutputType`]:
+    /// collections are ordered only when every element pair shares the same
+    /// ordering, and [`Statics`] compare through their own ordering. Unlike it, a
+    /// single unordered pair (e.g. a `NaN`) makes the whole comparison `None`.
+    pub fn approx_cmp(&self, a: &OutputType, b: &OutputType) -> Option<Ordering> {
+        match (a, b) {
+            (OutputType::Single(a), OutputType::Single(b)) => self.cmp_number(a, b),
+            (OutputType::Array(a), OutputType::Array(b)) => {
+                same_ordering(a, b, |a, b| self.cmp_number(a, b))
+            }
+            (OutputType::Custom(a), OutputType::Custom(b)) => {
+                same_ordering(a, b, |a, b| self.approx_cmp(a, b))
+            }
+            (OutputType::Array(a), OutputType::Custom(b)) => {
+                same_ordering(a, b, |a, b| self.approx_cmp(&OutputType::Single(*a), b))
+            }
+            (OutputType::Custom(a), OutputType::Array(b)) => {
+                same_ordering(a, b, |a, b| self.approx_cmp(a, &OutputType::Single(*b)))
+            }
+            (OutputType::Single(number), OutputType::Static(statics))
+            | (OutputType::Static(statics), OutputType::Single(number)) => {
+                statics.partial_cmp(number)
+            }
+            (OutputType::Array(numbers), OutputType::Statics(statics))
+            | (OutputType::Statics(statics), OutputType::Array(numbers)) => {
+                same_ordering(statics, numbers, |s: &Statics, n| s.partial_cmp(n))
+            }
+            (OutputType::Named(a), OutputType::Named(b)) => {
+                same_ordering(a, b, |(a_name, a), (b_name, b)| {
+                    (a_name == b_name).then(|| self.approx_cmp(a, b))?
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Wraps an output so `==`, `<` and `>` use this tolerance.
+    pub fn wrap<'a>(&self, value: &'a OutputType) -> Approx<'a> {
+        Approx {
+            value,
+            tolerance: *self,
+        }
+    }
+}
+
+/// Number of representable `f64` between two finite numbers.
+fn ulps_between(a: f64, b: f64) -> u64 {
+    // Maps the bits to integers that are ordered like the floats, -0.0 and 0.0 both to 0.
+    fn ordered(value: f64) -> i64 {
+        let bits = value.to_bits() as i64;
+        if bits < 0 { i64::MIN - bits } else { bits }
+    }
+    ordered(a).abs_diff(ordered(b))
+}
+
+fn same_ordering<A, B>(
+    a: &[A],
+    b: &[B],
+    cmp: impl Fn(&A, &B) -> Option<Ordering>,
+) -> Option<Ordering> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let mut orderings = a.iter().zip(b).map(|(a, b)| cmp(a, b));
+    let first = orderings.next()??;
+    orderings
+        .all(|ordering| ordering == Some(first))
+        .then_some(first)
+}
+
+/// An [`OutputType`] compared with a [`Tolerance`], see [`Tolerance::wrap`].
+///
+/// Handy in assertions and strategy conditions:
+/// `assert_eq!(tolerance.wrap(&actual), tolerance.wrap(&expected))`.
+/// The tolerance of the left hand side is used.
+#[derive(Clone, Copy)]
+pub struct Approx<'a> {
+    pub value: &'a OutputType,
+    pub tolerance: Tolerance,
+}
+
+impl fmt::Debug for Approx<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.value, f)
+    }
+}
+
+impl PartialEq for Approx<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.tolerance.approx_eq(self.value, other.value)
+    }
+}
+
+impl PartialEq<OutputType> for Approx<'_> {
+    fn eq(&self, other: &OutputType) -> bool {
+        self.tolerance.approx_eq(self.value, other)
+    }
+}
+
+impl PartialOrd for Approx<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.tolerance.approx_cmp(self.value, other.value)
+    }
+}
+
+impl PartialOrd<OutputType> for Approx<'_> {
+    fn partial_cmp(&self, other: &OutputType) -> Option<Ordering> {
+        self.tolerance.approx_cmp(self.value, other)
+    }
+}
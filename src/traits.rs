@@ -2,7 +2,11 @@ use core::fmt;
 
 use chipa_types::Number;
 
-use crate::{Bar, OutputShape, TaUtilsError, errors::TaUtilsResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::params::{ParamDescriptor, ParamMap};
+use crate::{Bar, OutputShape, TaUtilsError, Timeframe, errors::TaUtilsResult};
 
 pub trait Candle: fmt::Debug {
     fn open(&self) -> f64 {
@@ -27,14 +31,100 @@ pub trait Candle: fmt::Debug {
         f64::NAN
     }
 
+    /// Open time of the candle in milliseconds since the Unix epoch, if known.
+    fn timestamp(&self) -> Option<i64> {
+        None
+    }
+
+    fn timeframe(&self) -> Option<Timeframe> {
+        None
+    }
+
+    /// Median price, (high + low) / 2.
+    fn median_price(&self) -> f64 {
+        (self.high() + self.low()) / 2.0
+    }
+
+    /// Typical price, (high + low + close) / 3.
+    fn typical_price(&self) -> f64 {
+        (self.high() + self.low() + self.close()) / 3.0
+    }
+
+    /// Weighted close, (high + low + 2 * close) / 4.
+    fn weighted_close(&self) -> f64 {
+        (self.high() + self.low() + 2.0 * self.close()) / 4.0
+    }
+
+    /// Average price, (open + high + low + close) / 4.
+    fn ohlc4(&self) -> f64 {
+        (self.open() + self.high() + self.low() + self.close()) / 4.0
+    }
+
+    /// Distance between high and low.
+    fn range(&self) -> f64 {
+        self.high() - self.low()
+    }
+
+    /// True range, the range extended to the previous close if there is a previous candle.
+    fn true_range(&self, previous: Option<&dyn Candle>) -> f64 {
+        match previous {
+            Some(previous) => {
+                let close = previous.close();
+                self.high().max(close) - self.low().min(close)
+            }
+            None => self.range(),
+        }
+    }
+
+    /// Absolute distance between open and close.
+    fn body(&self) -> f64 {
+        (self.close() - self.open()).abs()
+    }
+
+    /// Distance between the high and the top of the body.
+    fn upper_wick(&self) -> f64 {
+        self.high() - self.open().max(self.close())
+    }
+
+    /// Distance between the bottom of the body and the low.
+    fn lower_wick(&self) -> f64 {
+        self.open().min(self.close()) - self.low()
+    }
+
+    fn is_bullish(&self) -> bool {
+        self.close() > self.open()
+    }
+
+    fn is_bearish(&self) -> bool {
+        self.close() < self.open()
+    }
+
+    /// Returns `true` if the body is at most `max_body_ratio` of the range.
+    fn is_doji(&self, max_body_ratio: f64) -> bool {
+        self.body() <= max_body_ratio * self.range()
+    }
+
+    /// Gap between this open and the previous close, positive for gaps up.
+    fn gap(&self, previous: &dyn Candle) -> f64 {
+        self.open() - previous.close()
+    }
+
     fn to_bar(&self) -> Bar {
-        Bar::new()
+        let mut bar = Bar::new()
             .set_open(self.open())
             .set_high(self.high())
             .set_low(self.low())
             .set_close(self.close())
             .set_price(self.price())
-            .set_volume(self.volume())
+            .set_volume(self.volume());
+        bar.open_time = self.timestamp();
+        bar.timeframe = self.timeframe();
+        // Left unknown when the close time does not fit in an `i64`
+        bar.close_time = bar
+            .open_time
+            .zip(bar.timeframe)
+            .and_then(|(open_time, timeframe)| open_time.checked_add(timeframe.as_millis()));
+        bar
     }
 }
 
@@ -42,6 +132,94 @@ pub trait Next<T> {
     type Output;
 
     fn next(&mut self, input: T) -> TaUtilsResult<Self::Output>;
+
+    /// Feeds `inputs` in order, writing each output to the slot of `outputs` with the same index.
+    ///
+    /// This is the hook behind [`NextBatch`], the default calls [`Next::next`] for
+    /// each input and indicators can override it with a vectorized implementation.
+    /// `outputs` is as long as `inputs`. On error, the outputs of the inputs before
+    /// the failing one are written and the error is wrapped in [`TaUtilsError::Batch`].
+    fn next_slice(&mut self, inputs: &[T], outputs: &mut [Self::Output]) -> TaUtilsResult<()>
+    where
+        T: Clone,
+    {
+        for (index, (input, output)) in inputs.iter().zip(outputs).enumerate() {
+            *output = self.next(input.clone()).map_err(|err| err.at_index(index))?;
+        }
+        Ok(())
+    }
+}
+
+/// Intrabar updates, replacing the most recent input instead of adding a new one.
+///
+/// Feed the first tick of a forming candle with [`Next::next`] and its following
+/// ticks with [`Update::update`], the next call to `next` commits the candle and
+/// starts a new one.
+pub trait Update<T>: Next<T> {
+    /// Returns the output as if `input` had been given instead of the last input,
+    /// behaves like `next` if there was none.
+    fn update(&mut self, input: T) -> TaUtilsResult<Self::Output>;
+}
+
+/// Hypothetical evaluation, the output [`Next::next`] would return for an input
+/// without changing any state.
+pub trait Peek<T>: Next<T> {
+    fn peek(&self, input: T) -> TaUtilsResult<Self::Output>;
+}
+
+/// Batch evaluation of any [`Next`], used to backfill indicators from history.
+pub trait NextBatch<T: Clone>: Next<T> {
+    /// Feeds every input into a pre-allocated buffer of the same length, see [`Next::next_slice`].
+    fn next_batch(&mut self, inputs: &[T], outputs: &mut [Self::Output]) -> TaUtilsResult<()> {
+        if inputs.len() != outputs.len() {
+            return Err(TaUtilsError::InvalidParameter(format!(
+                "Output buffer of length {} for {} inputs",
+                outputs.len(),
+                inputs.len()
+            )));
+        }
+        self.next_slice(inputs, outputs)
+    }
+
+    /// Feeds every input and collects the outputs.
+    fn next_batch_vec(&mut self, inputs: &[T]) -> TaUtilsResult<Vec<Self::Output>>
+    where
+        Self::Output: Default,
+    {
+        let mut outputs = Vec::with_capacity(inputs.len());
+        outputs.resize_with(inputs.len(), Default::default);
+        self.next_slice(inputs, &mut outputs)?;
+        Ok(outputs)
+    }
+}
+
+impl<T: Clone, N: Next<T> + ?Sized> NextBatch<T> for N {}
+
+impl<T, N: Peek<T> + ?Sized> Peek<T> for &mut N {
+    fn peek(&self, input: T) -> TaUtilsResult<Self::Output> {
+        (**self).peek(input)
+    }
+}
+
+impl<T, N: Update<T> + ?Sized> Update<T> for &mut N {
+    fn update(&mut self, input: T) -> TaUtilsResult<Self::Output> {
+        (**self).update(input)
+    }
+}
+
+impl<T, N: Next<T> + ?Sized> Next<T> for &mut N {
+    type Output = N::Output;
+
+    fn next(&mut self, input: T) -> TaUtilsResult<Self::Output> {
+        (**self).next(input)
+    }
+
+    fn next_slice(&mut self, inputs: &[T], outputs: &mut [Self::Output]) -> TaUtilsResult<()>
+    where
+        T: Clone,
+    {
+        (**self).next_slice(inputs, outputs)
+    }
 }
 
 impl Next<&dyn Candle> for () {
@@ -57,9 +235,46 @@ impl Next<&dyn Candle> for () {
 pub trait IndicatorTrait: fmt::Debug + fmt::Display + Reset + Period {
     fn output_shape(&self) -> OutputShape;
 
+    /// Describes the parameters of the indicator, see [`Parameters`](crate::Parameters).
+    fn param_descriptors(&self) -> Vec<ParamDescriptor> {
+        Vec::new()
+    }
+
+    /// Current value of the parameters of the indicator.
+    fn param_values(&self) -> ParamMap {
+        ParamMap::new()
+    }
+
     fn name(&self) -> String {
         self.to_string()
     }
+
+    /// Warm-up tracking of the indicator, `None` if it does not implement [`Readiness`].
+    ///
+    /// Kept as an accessor rather than a supertrait so indicators without a
+    /// warm-up notion still implement this trait, implementors of both return
+    /// `Some(self)`.
+    fn readiness(&self) -> Option<&dyn Readiness> {
+        None
+    }
+}
+
+/// Warm-up tracking, whether an indicator has seen enough inputs for its outputs
+/// to be meaningful. See [`Warmup`](crate::Warmup) and [`IndicatorTrait::readiness`].
+pub trait Readiness: Period {
+    /// Number of inputs processed since the creation or the last reset, it may
+    /// stop growing once it reaches the warm-up period (e.g. a full window).
+    fn bars_seen(&self) -> usize;
+
+    /// Number of inputs needed before the outputs are meaningful, `period` unless
+    /// the indicator needs more to converge (e.g. exponential averages).
+    fn warmup_period(&self) -> usize {
+        self.period()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.bars_seen() >= self.warmup_period()
+    }
 }
 
 /// Resets an indicator to the initial state.
@@ -71,6 +286,107 @@ pub trait Period {
     fn period(&self) -> usize;
 }
 
+/// Captures and restores the full internal state of an indicator, so it can be
+/// persisted and resumed later without replaying its history.
+pub trait Snapshot {
+    /// Serializable copy of the state, usually a struct of the states of the fields.
+    type State: Serialize + DeserializeOwned;
+
+    /// Version of the layout of `State`, to bump whenever it changes.
+    const VERSION: u32 = 1;
+
+    fn snapshot(&self) -> Self::State;
+
+    /// Replaces the current state, fails if `state` does not fit the configuration
+    /// of the indicator (e.g. a window of another period).
+    fn restore(&mut self, state: Self::State) -> TaUtilsResult<()>;
+
+    /// Builds a new value from a state, used by containers to restore elements
+    /// they do not hold yet. Fails unless overridden, types whose state holds
+    /// their whole configuration should override it.
+    fn from_snapshot(state: Self::State) -> TaUtilsResult<Self>
+    where
+        Self: Sized,
+    {
+        let _ = state;
+        Err(TaUtilsError::InvalidParameter(format!(
+            "Cannot build a {} from a state",
+            core::any::type_name::<Self>()
+        )))
+    }
+
+    fn checkpoint(&self) -> Checkpoint<Self::State> {
+        Checkpoint {
+            version: Self::VERSION,
+            state: self.snapshot(),
+        }
+    }
+
+    /// Restores a checkpoint, fails if it was taken with another version of the state.
+    fn restore_checkpoint(&mut self, checkpoint: Checkpoint<Self::State>) -> TaUtilsResult<()> {
+        if checkpoint.version != Self::VERSION {
+            return Err(TaUtilsError::InvalidParameter(format!(
+                "Checkpoint version {} does not match the state version {}",
+                checkpoint.version,
+                Self::VERSION
+            )));
+        }
+        self.restore(checkpoint.state)
+    }
+}
+
+/// A versioned [`Snapshot`] state, the value to persist.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint<S> {
+    pub version: u32,
+    pub state: S,
+}
+
+/// Fails unless a restored state has the period of the indicator it is restored into.
+pub(crate) fn check_period(period: usize, state: usize) -> TaUtilsResult<()> {
+    if period != state {
+        return Err(TaUtilsError::InvalidParameter(format!(
+            "Cannot restore a state of period {state} into a period of {period}"
+        )));
+    }
+    Ok(())
+}
+
+/// Implements [`Snapshot`] for a type that is its own state, only restoring a
+/// state of the same [`Period`]. Generics go in brackets after `impl`, and the
+/// state version can follow the type.
+macro_rules! snapshot_period {
+    (impl [$($generics:tt)*] $ty:ty $(, $version:expr)?) => {
+        impl<$($generics)*> $crate::Snapshot for $ty {
+            type State = Self;
+            $(const VERSION: u32 = $version;)?
+
+            fn snapshot(&self) -> Self::State {
+                self.clone()
+            }
+
+            fn restore(&mut self, state: Self::State) -> $crate::TaUtilsResult<()> {
+                $crate::traits::check_period(
+                    $crate::Period::period(self),
+                    $crate::Period::period(&state),
+                )?;
+                *self = state;
+                Ok(())
+            }
+
+            fn from_snapshot(state: Self::State) -> $crate::TaUtilsResult<Self> {
+                Ok(state)
+            }
+        }
+    };
+    ($ty:ty $(, $version:expr)?) => {
+        $crate::traits::snapshot_period!(impl [] $ty $(, $version)?);
+    };
+}
+
+pub(crate) use snapshot_period;
+
 
 // Implement Reset for default types
 impl Reset for () {
@@ -176,3 +492,168 @@ impl<T> Period for Box<[T]> {
     }
 }
 
+// Implement Snapshot for default types, their state is a copy of themselves
+macro_rules! snapshot_clone {
+    ($($ty:ty),*) => {
+        $(
+            impl Snapshot for $ty {
+                type State = Self;
+
+                fn snapshot(&self) -> Self::State {
+                    self.clone()
+                }
+
+                fn restore(&mut self, state: Self::State) -> TaUtilsResult<()> {
+                    *self = state;
+                    Ok(())
+                }
+
+                fn from_snapshot(state: Self::State) -> TaUtilsResult<Self> {
+                    Ok(state)
+                }
+            }
+        )*
+    };
+}
+
+snapshot_clone!((), f64, Number, usize, bool, String);
+
+/// Elements are restored in place, missing ones are built with [`Snapshot::from_snapshot`].
+impl<T: Snapshot> Snapshot for Vec<T> {
+    type State = Vec<T::State>;
+
+    const VERSION: u32 = T::VERSION;
+
+    fn snapshot(&self) -> Self::State {
+        self.iter().map(T::snapshot).collect()
+    }
+
+    fn restore(&mut self, state: Self::State) -> TaUtilsResult<()> {
+        self.truncate(state.len());
+        let mut state = state.into_iter();
+        for (value, state) in self.iter_mut().zip(state.by_ref()) {
+            value.restore(state)?;
+        }
+        for state in state {
+            self.push(T::from_snapshot(state)?);
+        }
+        Ok(())
+    }
+
+    fn from_snapshot(state: Self::State) -> TaUtilsResult<Self> {
+        state.into_iter().map(T::from_snapshot).collect()
+    }
+}
+
+/// The value is restored in place, or built with [`Snapshot::from_snapshot`] when `None`.
+impl<T: Snapshot> Snapshot for Option<T> {
+    type State = Option<T::State>;
+
+    const VERSION: u32 = T::VERSION;
+
+    fn snapshot(&self) -> Self::State {
+        self.as_ref().map(T::snapshot)
+    }
+
+    fn restore(&mut self, state: Self::State) -> TaUtilsResult<()> {
+        match (self.as_mut(), state) {
+            (Some(value), Some(state)) => value.restore(state),
+            (None, Some(state)) => {
+                *self = Some(T::from_snapshot(state)?);
+                Ok(())
+            }
+            (_, None) => {
+                *self = None;
+                Ok(())
+            }
+        }
+    }
+
+    fn from_snapshot(state: Self::State) -> TaUtilsResult<Self> {
+        state.map(T::from_snapshot).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::{Doji, PatternConfig};
+    use crate::{Bar, Queue, RollingMean, Warmup};
+
+    #[test]
+    fn restores_containers_into_new_values() {
+        let mut means = vec![RollingMean::new(2).unwrap(), RollingMean::new(3).unwrap()];
+        means[0].next(1.0).unwrap();
+        means[1].next(2.0).unwrap();
+        let state = means.snapshot();
+
+        let mut empty = Vec::<RollingMean>::new();
+        empty.restore(state.clone()).unwrap();
+        assert_eq!(empty, means);
+        let mut longer = vec![
+            RollingMean::new(2).unwrap(),
+            RollingMean::new(3).unwrap(),
+            RollingMean::new(4).unwrap(),
+        ];
+        longer.restore(state.clone()).unwrap();
+        assert_eq!(longer, means);
+        let mut mismatched = vec![RollingMean::new(5).unwrap()];
+        assert!(mismatched.restore(state).is_err());
+
+        let mut lazy: Option<Queue<f64>> = None;
+        let mut queue = Queue::new(2).unwrap();
+        queue.push(1.0);
+        lazy.restore(Some(queue.snapshot())).unwrap();
+        assert_eq!(lazy, Some(queue));
+        lazy.restore(None).unwrap();
+        assert_eq!(lazy, None);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut value = 1.0;
+        let checkpoint = Checkpoint { version: 2, state: 3.0 };
+        assert!(value.restore_checkpoint(checkpoint).is_err());
+        value.restore_checkpoint(Checkpoint { version: 1, state: 3.0 }).unwrap();
+        assert_eq!(value, 3.0);
+    }
+
+    fn warmed_up<N: IndicatorTrait>(indicator: &N) -> Option<bool> {
+        indicator.readiness().map(Readiness::is_ready)
+    }
+
+    #[test]
+    fn reaches_readiness_from_indicators() {
+        let mut doji = Doji::new(PatternConfig::default()).unwrap();
+        assert_eq!(warmed_up(&doji), Some(false));
+        doji.next(&Bar::new().set_open(1.0).set_close(1.0)).unwrap();
+        assert_eq!(warmed_up(&doji), Some(true));
+        assert_eq!(warmed_up(&Warmup::new(doji)), Some(true));
+    }
+
+    #[derive(Debug)]
+    struct Tick(i64, Timeframe);
+
+    impl Candle for Tick {
+        fn price(&self) -> f64 {
+            1.0
+        }
+
+        fn timestamp(&self) -> Option<i64> {
+            Some(self.0)
+        }
+
+        fn timeframe(&self) -> Option<Timeframe> {
+            Some(self.1)
+        }
+    }
+
+    #[test]
+    fn leaves_overflowing_close_times_unknown() {
+        let minute = Timeframe::minutes(1).unwrap();
+        assert_eq!(Tick(1_000, minute).to_bar().close_time, Some(61_000));
+        let bar = Tick(i64::MAX - 1_000, minute).to_bar();
+        assert_eq!(bar.open_time, Some(i64::MAX - 1_000));
+        assert_eq!(bar.close_time, None);
+    }
+}
@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+
+use crate::resample::add_volume;
+use crate::{Bar, Candle, Next, Period, Reset, TaUtilsError, TaUtilsResult};
+
+/// Converts raw candles into Heikin-Ashi candles, one per input.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HeikinAshi {
+    previous: Option<(f64, f64)>,
+}
+
+impl HeikinAshi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Next<&dyn Candle> for HeikinAshi {
+    type Output = Vec<Bar>;
+
+    fn next(&mut self, input: &dyn Candle) -> TaUtilsResult<Self::Output> {
+        let close = input.ohlc4();
+        let open = match self.previous {
+            Some((open, close)) => (open + close) / 2.0,
+            None => (input.open() + input.close()) / 2.0,
+        };
+        self.previous = Some((open, close));
+        let mut bar = input.to_bar();
+        bar.open = open;
+        bar.high = input.high().max(open).max(close);
+        bar.low = input.low().min(open).min(close);
+        bar.close = close;
+        bar.price = close;
+        Ok(vec![bar])
+    }
+}
+
+impl Period for HeikinAshi {
+    fn period(&self) -> usize {
+        1
+    }
+}
+
+impl Reset for HeikinAshi {
+    fn reset(&mut self) {
+        self.previous = None;
+    }
+}
+
+/// How the brick size of a [`Renko`] chart is chosen.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BoxSize {
+    /// Constant brick size in price units.
+    Fixed(f64),
+    /// Brick size equal to the current Wilder ATR over the given period.
+    Atr(usize),
+}
+
+/// Wilder's average true range.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Atr {
+    period: usize,
+    count: usize,
+    value: f64,
+    previous_close: Option<f64>,
+}
+
+impl Atr {
+    fn new(period: usize) -> Self {
+        Self {
+            period,
+            count: 0,
+            value: 0.0,
+            previous_close: None,
+        }
+    }
+
+    fn next(&mut self, input: &dyn Candle) -> Option<f64> {
+        let true_range = match self.previous_close {
+            Some(close) => input.high().max(close) - input.low().min(close),
+            None => input.range(),
+        };
+        self.previous_close = Some(input.close());
+        self.count += 1;
+        let n = self.period as f64;
+        if self.count <= self.period {
+            self.value += true_range / n;
+        } else {
+            self.value = (self.value * (n - 1.0) + true_range) / n;
+        }
+        (self.count >= self.period).then_some(self.value)
+    }
+}
+
+/// Converts candles into Renko bricks built from their close, emitting zero or more bricks per input.
+///
+/// New bricks start from the top or the bottom of the last brick, so a brick in
+/// the same direction needs a move of one box beyond the last close while a
+/// reversal needs a move of one box beyond the last open. With [`BoxSize::Atr`] no brick is emitted until the
+/// ATR is warmed up, then each brick uses the ATR at the time it is formed.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Renko {
+    box_size: BoxSize,
+    atr: Option<Atr>,
+    /// Close of the first input, used as reference until the first brick.
+    anchor: Option<f64>,
+    /// Open and close of the last brick.
+    last: Option<(f64, f64)>,
+    volume: f64,
+}
+
+impl Renko {
+    pub fn new(box_size: BoxSize) -> TaUtilsResult<Self> {
+        let atr = match box_size {
+            BoxSize::Fixed(size) if size.is_finite() && size > 0.0 => None,
+            BoxSize::Atr(period) if period > 0 => Some(Atr::new(period)),
+            _ => {
+                return Err(TaUtilsError::InvalidParameter(format!(
+                    "Invalid Renko box size {box_size:?}"
+                )));
+            }
+        };
+        Ok(Self {
+            box_size,
+            atr,
+            anchor: None,
+            last: None,
+            volume: f64::NAN,
+        })
+    }
+
+    pub fn box_size(&self) -> BoxSize {
+        self.box_size
+    }
+
+    fn brick(&mut self, input: &dyn Candle, open: f64, close: f64) -> Bar {
+        self.last = Some((open, close));
+        let mut bar = Bar::new()
+            .set_open(open)
+            .set_high(open.max(close))
+            .set_low(open.min(close))
+            .set_close(close)
+            .set_price(close)
+            .set_volume(std::mem::replace(&mut self.volume, 0.0));
+        bar.open_time = input.timestamp();
+        bar
+    }
+}
+
+impl Next<&dyn Candle> for Renko {
+    type Output = Vec<Bar>;
+
+    fn next(&mut self, input: &dyn Candle) -> TaUtilsResult<Self::Output> {
+        let price = finite_close(input)?;
+        let size = match (self.box_size, self.atr.as_mut()) {
+            (BoxSize::Fixed(size), _) => Some(size),
+            (BoxSize::Atr(_), Some(atr)) => atr.next(input).filter(|size| *size > 0.0),
+            (BoxSize::Atr(_), None) => None,
+        };
+        self.volume = add_volume(self.volume, input.volume());
+        let anchor = *self.anchor.get_or_insert(price);
+        let Some(size) = size else {
+            return Ok(Vec::new());
+        };
+
+        let mut bricks = Vec::new();
+        loop {
+            let (bottom, top) = match self.last {
+                Some((open, close)) => (open.min(close), open.max(close)),
+                None => (anchor, anchor),
+            };
+            // At large magnitudes adding the box size can round back to the same
+            // boundary, stop there instead of emitting empty bricks forever.
+            if price >= top + size && top + size > top {
+                let brick = self.brick(input, top, top + size);
+                bricks.push(brick);
+            } else if price <= bottom - size && bottom - size < bottom {
+                let brick = self.brick(input, bottom, bottom - size);
+                bricks.push(brick);
+            } else {
+                break;
+            }
+        }
+        Ok(bricks)
+    }
+}
+
+impl Period for Renko {
+    fn period(&self) -> usize {
+        match self.box_size {
+            BoxSize::Fixed(_) => 1,
+            BoxSize::Atr(period) => period,
+        }
+    }
+}
+
+impl Reset for Renko {
+    fn reset(&mut self) {
+        if let BoxSize::Atr(period) = self.box_size {
+            self.atr = Some(Atr::new(period));
+        }
+        self.anchor = None;
+        self.last = None;
+        self.volume = f64::NAN;
+    }
+}
+
+/// Converts candles into range bars spanning exactly `range` between high and low,
+/// built from the close of each input. Large moves emit several bars at once.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RangeBars {
+    range: f64,
+    current: Option<Bar>,
+}
+
+impl RangeBars {
+    pub fn new(range: f64) -> TaUtilsResult<Self> {
+        if !range.is_finite() || range <= 0.0 {
+            return Err(TaUtilsError::InvalidParameter(format!(
+                "Range must be a positive number, got {range}"
+            )));
+        }
+        Ok(Self {
+            range,
+            current: None,
+        })
+    }
+
+    pub fn range(&self) -> f64 {
+        self.range
+    }
+
+    /// The bar currently being built, if any.
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+}
+
+impl Next<&dyn Candle> for RangeBars {
+    type Output = Vec<Bar>;
+
+    fn next(&mut self, input: &dyn Candle) -> TaUtilsResult<Self::Output> {
+        let price = finite_close(input)?;
+        let start = |open: f64| {
+            let mut bar = Bar::new()
+                .set_open(open)
+                .set_high(open)
+                .set_low(open)
+                .set_close(open)
+                .set_price(open)
+                .set_volume(f64::NAN);
+            bar.open_time = input.timestamp();
+            bar
+        };
+        let mut bar = self.current.take().unwrap_or_else(|| start(price));
+        bar.volume = add_volume(bar.volume, input.volume());
+
+        let mut bars = Vec::new();
+        loop {
+            // Same as Renko, a boundary that rounds back onto the bar does not close it.
+            let boundary = if price >= bar.low + self.range && bar.low + self.range > bar.low {
+                bar.low + self.range
+            } else if price <= bar.high - self.range && bar.high - self.range < bar.high {
+                bar.high - self.range
+            } else {
+                break;
+            };
+            bar.high = bar.high.max(boundary);
+            bar.low = bar.low.min(boundary);
+            bar.close = boundary;
+            bar.price = boundary;
+            bar.close_time = input.timestamp();
+            let next = start(boundary);
+            bars.push(std::mem::replace(&mut bar, next));
+        }
+        bar.high = bar.high.max(price);
+        bar.low = bar.low.min(price);
+        bar.close = price;
+        bar.price = price;
+        self.current = Some(bar);
+        Ok(bars)
+    }
+}
+
+impl Period for RangeBars {
+    fn period(&self) -> usize {
+        1
+    }
+}
+
+impl Reset for RangeBars {
+    fn reset(&mut self) {
+        self.current = None;
+    }
+}
+
+/// Close of `input`, rejecting non finite values which would emit bars forever.
+fn finite_close(input: &dyn Candle) -> TaUtilsResult<f64> {
+    let close = input.close();
+    if !close.is_finite() {
+        return Err(TaUtilsError::InvalidParameter(format!(
+            "Cannot build bars from a non finite close {close}"
+        )));
+    }
+    Ok(close)
+}
+
+/// Feeds every bar emitted by a transformer such as [`HeikinAshi`], [`Renko`]
+/// or [`RangeBars`] into a downstream `Next<&dyn Candle>` consumer, returning
+/// the consumer output for each of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transformed<T, N> {
+    transformer: T,
+    inner: N,
+}
+
+impl<T, N> Transformed<T, N> {
+    pub fn new(transformer: T, inner: N) -> Self {
+        Self { transformer, inner }
+    }
+
+    pub fn transformer(&self) -> &T {
+        &self.transformer
+    }
+
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    pub fn into_parts(self) -> (T, N) {
+        (self.transformer, self.inner)
+    }
+}
+
+impl<'a, T, N, O> Next<&'a dyn Candle> for Transformed<T, N>
+where
+    T: for<'b> Next<&'b dyn Candle, Output = Vec<Bar>>,
+    N: for<'b> Next<&'b dyn Candle, Output = O>,
+{
+    type Output = Vec<O>;
+
+    fn next(&mut self, input: &'a dyn Candle) -> TaUtilsResult<Self::Output> {
+        self.transformer
+            .next(input)?
+            .iter()
+            .map(|bar| self.inner.next(bar))
+            .collect()
+    }
+}
+
+impl<T: Period, N: Period> Period for Transformed<T, N> {
+    fn period(&self) -> usize {
+        self.transformer.period().max(self.inner.period())
+    }
+}
+
+impl<T: Reset, N: Reset> Reset for Transformed<T, N> {
+    fn reset(&mut self) {
+        self.transformer.reset();
+        self.inner.reset();
+    }
+}
@@ -1,20 +1,59 @@
+use std::cmp::Ordering;
 use std::fmt;
-use std::ops::{Deref, DerefMut};
+use std::iter::{self, Chain, Once, Skip};
+use std::ops::Index;
+use std::slice;
+use std::str::FromStr;
 
 use chipa_types::Number;
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeStruct;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
 
+use crate::errors::BarError;
+use crate::traits::snapshot_period;
 use crate::{Candle, Period, Reset, TaUtilsError, TaUtilsResult};
 
+mod extrema;
+mod rolling;
+
+pub use extrema::{Extrema, Extreme, RollingExtrema};
+pub use rolling::{
+    Moments, RollingCorrelation, RollingCovariance, RollingMean, RollingStats, RollingStdDev,
+    RollingSum, RollingVariance, RollingZScore,
+};
+
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, PartialOrd)]
 pub struct Bar {
+    #[serde(deserialize_with = "nan_if_null")]
     pub open: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub high: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub low: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub close: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub price: f64,
+    #[serde(deserialize_with = "nan_if_null")]
     pub volume: f64,
+    /// Open time of the bar in milliseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_time: Option<i64>,
+    /// Close time of the bar in milliseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_time: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeframe: Option<Timeframe>,
+}
+
+/// Duration covered by a single bar, e.g. `1s`, `5m`, `1h` or `1d`.
+///
+/// Serialized as a string made of a count and a unit (`s`, `m`, `h`, `d` or `w`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timeframe {
+    seconds: u64,
 }
 
 /// Market data passed to strategies and indicators.
@@ -24,18 +63,86 @@ pub struct Bar {
 pub enum MarketData {
     Bar(Bar), // Boxed trait object for dynamic dispatch
     // Add more variants as needed for other Candle implementors
-    Float(f64), // TODO: Use Decimal in the future
+    Float(#[serde(deserialize_with = "nan_if_null")] f64), // TODO: Use Decimal in the future
+    Trade(Trade),
+    Quote(Quote),
+}
+
+/// Side of the aggressor of a trade.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
 }
 
-// Can you help me emprove the Queue struct? the goal is to make it like a Vec but with a fixed capacity that removes the oldest element when a new one is added beyond its capacity.
-// it also implements the Period and Reset traits, allowing it to be used in a similar way to Cycle.
+/// A single executed trade.
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, PartialOrd)]
+pub struct Trade {
+    #[serde(deserialize_with = "nan_if_null")]
+    pub price: f64,
+    #[serde(deserialize_with = "nan_if_null")]
+    pub size: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    /// Time of the trade in milliseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+}
+
+/// Top of the book bid and ask quote.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, PartialOrd)]
+pub struct Quote {
+    #[serde(deserialize_with = "nan_if_null")]
+    pub bid: f64,
+    #[serde(deserialize_with = "nan_if_null")]
+    pub ask: f64,
+    #[serde(deserialize_with = "nan_if_null")]
+    pub bid_size: f64,
+    #[serde(deserialize_with = "nan_if_null")]
+    pub ask_size: f64,
+    /// Time of the quote in milliseconds since the Unix epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+}
+
+/// Fixed capacity FIFO window backed by a ring buffer.
+///
+/// Once `period` elements are stored, every `push` evicts the oldest element
+/// in O(1). Elements are always exposed in chronological order (oldest first).
+#[derive(Clone)]
 pub struct Queue<T> {
-    queue: Vec<T>,
+    buf: Vec<T>,
+    /// Index in `buf` of the oldest element, only non zero once the queue is full.
+    head: usize,
     period: usize,
 }
 
+/// Chronological iterator over the elements of a [`Queue`].
+#[derive(Debug, Clone)]
+pub struct QueueIter<'a, T> {
+    inner: Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>,
+}
+
+/// Read only view of a [`Queue`] as it would be after pushing a value, see [`Queue::peek_push`].
+#[derive(Debug)]
+pub struct PeekQueue<'a, T> {
+    queue: &'a Queue<T>,
+    value: &'a T,
+}
+
+/// `serde_json` writes non finite floats as `null`, read them back as `NaN` so
+/// missing volumes and sizes survive a round trip.
+fn nan_if_null<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    if deserializer.is_human_readable() {
+        Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+    } else {
+        f64::deserialize(deserializer)
+    }
+}
+
 impl Default for Bar {
     fn default() -> Self {
         Self {
@@ -45,6 +152,9 @@ impl Default for Bar {
             high: 0.0,
             price: 0.0,
             volume: 0.0,
+            open_time: None,
+            close_time: None,
+            timeframe: None,
         }
     }
 }
@@ -84,8 +194,71 @@ impl Bar {
         self
     }
 
+    pub fn set_open_time(mut self, val: i64) -> Self {
+        self.open_time = Some(val);
+        self
+    }
+
+    pub fn set_close_time(mut self, val: i64) -> Self {
+        self.close_time = Some(val);
+        self
+    }
+
+    pub fn set_timeframe(mut self, val: Timeframe) -> Self {
+        self.timeframe = Some(val);
+        self
+    }
+
+    /// Returns the typical price ((high + low + close) / 3), see [`Candle::typical_price`].
     pub fn typical_price(&self) -> f64 {
-        (self.high + self.low + self.close) / 3.0
+        Candle::typical_price(self)
+    }
+
+    /// Checks the OHLCV invariants: finite prices, `low <= high`, open, close and
+    /// price inside `[low, high]` and a non negative volume. A `NaN` volume is
+    /// accepted as it marks a missing volume.
+    pub fn validate(&self) -> TaUtilsResult<()> {
+        let prices = [
+            ("open", self.open),
+            ("high", self.high),
+            ("low", self.low),
+            ("close", self.close),
+            ("price", self.price),
+        ];
+        for (field, value) in prices {
+            if !value.is_finite() {
+                return Err(BarError::NonFinite { field, value }.into());
+            }
+        }
+        if self.volume.is_infinite() {
+            return Err(BarError::NonFinite {
+                field: "volume",
+                value: self.volume,
+            }
+            .into());
+        }
+        if self.high < self.low {
+            return Err(BarError::HighBelowLow {
+                high: self.high,
+                low: self.low,
+            }
+            .into());
+        }
+        for (field, value) in [prices[0], prices[3], prices[4]] {
+            if value < self.low || value > self.high {
+                return Err(BarError::OutOfRange {
+                    field,
+                    value,
+                    low: self.low,
+                    high: self.high,
+                }
+                .into());
+            }
+        }
+        if self.volume < 0.0 {
+            return Err(BarError::NegativeVolume(self.volume).into());
+        }
+        Ok(())
     }
 }
 
@@ -113,6 +286,18 @@ impl Candle for Bar {
     fn volume(&self) -> f64 {
         self.volume
     }
+
+    fn timestamp(&self) -> Option<i64> {
+        self.open_time
+    }
+
+    fn timeframe(&self) -> Option<Timeframe> {
+        self.timeframe
+    }
+
+    fn to_bar(&self) -> Bar {
+        self.clone()
+    }
 }
 
 impl fmt::Display for Bar {
@@ -125,12 +310,157 @@ impl fmt::Display for Bar {
     }
 }
 
+impl Trade {
+    pub fn new(price: f64, size: f64) -> Self {
+        Self {
+            price,
+            size,
+            side: None,
+            timestamp: None,
+        }
+    }
+
+    pub fn set_side(mut self, val: Side) -> Self {
+        self.side = Some(val);
+        self
+    }
+
+    pub fn set_timestamp(mut self, val: i64) -> Self {
+        self.timestamp = Some(val);
+        self
+    }
+
+    /// Size signed by the aggressor side, positive for buys, negative for sells and `0` if unknown.
+    pub fn signed_size(&self) -> f64 {
+        match self.side {
+            Some(Side::Buy) => self.size,
+            Some(Side::Sell) => -self.size,
+            None => 0.0,
+        }
+    }
+}
+
+impl Candle for Trade {
+    fn price(&self) -> f64 {
+        self.price
+    }
+
+    fn volume(&self) -> f64 {
+        self.size
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        self.timestamp
+    }
+}
+
+impl fmt::Display for Trade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = match self.side {
+            Some(Side::Buy) => "Buy",
+            Some(Side::Sell) => "Sell",
+            None => "Unknown",
+        };
+        write!(
+            f,
+            "Trade {{ P: {:.2}, S: {:.0}, Side: {} }}",
+            self.price, self.size, side
+        )
+    }
+}
+
+impl Quote {
+    pub fn new(bid: f64, ask: f64) -> Self {
+        Self {
+            bid,
+            ask,
+            bid_size: f64::NAN,
+            ask_size: f64::NAN,
+            timestamp: None,
+        }
+    }
+
+    pub fn set_bid_size(mut self, val: f64) -> Self {
+        self.bid_size = val;
+        self
+    }
+
+    pub fn set_ask_size(mut self, val: f64) -> Self {
+        self.ask_size = val;
+        self
+    }
+
+    pub fn set_timestamp(mut self, val: i64) -> Self {
+        self.timestamp = Some(val);
+        self
+    }
+
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+
+    pub fn spread(&self) -> f64 {
+        self.ask - self.bid
+    }
+
+    /// Spread relative to the mid price.
+    pub fn relative_spread(&self) -> f64 {
+        self.spread() / self.mid()
+    }
+
+    /// Order book imbalance in `[-1, 1]`, positive when there is more size on the bid.
+    pub fn imbalance(&self) -> f64 {
+        (self.bid_size - self.ask_size) / (self.bid_size + self.ask_size)
+    }
+}
+
+impl Candle for Quote {
+    fn price(&self) -> f64 {
+        self.mid()
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        self.timestamp
+    }
+}
+
+impl fmt::Display for Quote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Quote {{ B: {:.2}, A: {:.2}, BS: {:.0}, AS: {:.0} }}",
+            self.bid, self.ask, self.bid_size, self.ask_size
+        )
+    }
+}
+
 impl MarketData {
-    /// Returns the typical price ((high + low + close) / 3).
+    /// Returns the typical price ((high + low + close) / 3), see [`Candle::typical_price`].
     pub fn typical_price(&self) -> f64 {
+        Candle::typical_price(self)
+    }
+
+    /// Bid/ask spread, only available for quotes.
+    pub fn spread(&self) -> Option<f64> {
         match self {
-            MarketData::Bar(bar) => bar.typical_price(),
-            MarketData::Float(value) => *value,
+            MarketData::Quote(quote) => Some(quote.spread()),
+            _ => None,
+        }
+    }
+
+    /// Traded size, only available for trades.
+    pub fn size(&self) -> Option<f64> {
+        match self {
+            MarketData::Trade(trade) => Some(trade.size),
+            _ => None,
+        }
+    }
+
+    /// Aggressor side, only available for trades that carry it.
+    pub fn side(&self) -> Option<Side> {
+        match self {
+            MarketData::Trade(trade) => trade.side,
+            _ => None,
         }
     }
     
@@ -142,6 +472,7 @@ impl MarketData {
             close: close.into().into(),
             price: price.into().into(),
             volume: volume.into().into(),
+            ..Bar::default()
         })
     }
 }
@@ -151,6 +482,8 @@ impl Candle for MarketData {
         match self {
             MarketData::Bar(bar) => bar.open(),
             MarketData::Float(value) => *value,
+            MarketData::Trade(trade) => trade.open(),
+            MarketData::Quote(quote) => quote.open(),
         }
     }
 
@@ -158,6 +491,8 @@ impl Candle for MarketData {
         match self {
             MarketData::Bar(bar) => bar.close(),
             MarketData::Float(value) => *value,
+            MarketData::Trade(trade) => trade.close(),
+            MarketData::Quote(quote) => quote.close(),
         }
     }
 
@@ -165,6 +500,8 @@ impl Candle for MarketData {
         match self {
             MarketData::Bar(bar) => bar.high(),
             MarketData::Float(value) => *value,
+            MarketData::Trade(trade) => trade.high(),
+            MarketData::Quote(quote) => quote.high(),
         }
     }
 
@@ -172,6 +509,8 @@ impl Candle for MarketData {
         match self {
             MarketData::Bar(bar) => bar.low(),
             MarketData::Float(value) => *value,
+            MarketData::Trade(trade) => trade.low(),
+            MarketData::Quote(quote) => quote.low(),
         }
     }
 
@@ -179,6 +518,8 @@ impl Candle for MarketData {
         match self {
             MarketData::Bar(bar) => bar.price(),
             MarketData::Float(value) => *value,
+            MarketData::Trade(trade) => trade.price(),
+            MarketData::Quote(quote) => quote.price(),
         }
     }
 
@@ -186,6 +527,39 @@ impl Candle for MarketData {
         match self {
             MarketData::Bar(bar) => bar.volume(),
             MarketData::Float(_) => f64::NAN, // Volume not applicable for Float variant
+            MarketData::Trade(trade) => trade.volume(),
+            MarketData::Quote(quote) => quote.volume(),
+        }
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        match self {
+            MarketData::Bar(bar) => bar.timestamp(),
+            MarketData::Float(_) => None,
+            MarketData::Trade(trade) => trade.timestamp(),
+            MarketData::Quote(quote) => quote.timestamp(),
+        }
+    }
+
+    fn timeframe(&self) -> Option<Timeframe> {
+        match self {
+            MarketData::Bar(bar) => bar.timeframe(),
+            _ => None,
+        }
+    }
+
+    fn to_bar(&self) -> Bar {
+        match self {
+            MarketData::Bar(bar) => bar.clone(),
+            MarketData::Float(value) => Bar::new()
+                .set_open(*value)
+                .set_high(*value)
+                .set_low(*value)
+                .set_close(*value)
+                .set_price(*value)
+                .set_volume(f64::NAN),
+            MarketData::Trade(trade) => trade.to_bar(),
+            MarketData::Quote(quote) => quote.to_bar(),
         }
     }
 }
@@ -195,10 +569,125 @@ impl fmt::Display for MarketData {
         match self {
             MarketData::Bar(bar) => write!(f, "{}", bar),
             MarketData::Float(value) => write!(f, "Float({:.2})", value),
+            MarketData::Trade(trade) => write!(f, "{}", trade),
+            MarketData::Quote(quote) => write!(f, "{}", quote),
         }
     }
 }
 
+impl Timeframe {
+    /// Longest supported timeframe, the longest one whose milliseconds fit in an `i64`.
+    pub const MAX_SECONDS: u64 = i64::MAX as u64 / 1000;
+
+    /// A timeframe of `count` seconds, which must be between 1 and [`Timeframe::MAX_SECONDS`].
+    pub fn seconds(count: u64) -> TaUtilsResult<Self> {
+        if count == 0 || count > Self::MAX_SECONDS {
+            return Err(TaUtilsError::InvalidParameter(format!(
+                "Timeframe must be between 1 and {} seconds, got {count}",
+                Self::MAX_SECONDS
+            )));
+        }
+        Ok(Self { seconds: count })
+    }
+
+    pub fn minutes(count: u64) -> TaUtilsResult<Self> {
+        Self::scaled(count, 60, "minutes")
+    }
+
+    pub fn hours(count: u64) -> TaUtilsResult<Self> {
+        Self::scaled(count, 3_600, "hours")
+    }
+
+    pub fn days(count: u64) -> TaUtilsResult<Self> {
+        Self::scaled(count, 86_400, "days")
+    }
+
+    pub fn weeks(count: u64) -> TaUtilsResult<Self> {
+        Self::scaled(count, 604_800, "weeks")
+    }
+
+    fn scaled(count: u64, unit: u64, name: &str) -> TaUtilsResult<Self> {
+        let seconds = count.checked_mul(unit).ok_or_else(|| {
+            TaUtilsError::InvalidParameter(format!("Timeframe of {count} {name} is too long"))
+        })?;
+        Self::seconds(seconds)
+    }
+
+    pub const fn as_secs(&self) -> u64 {
+        self.seconds
+    }
+
+    /// Length in milliseconds, never overflows as the seconds are bounded by [`Timeframe::MAX_SECONDS`].
+    pub const fn as_millis(&self) -> i64 {
+        self.seconds as i64 * 1000
+    }
+
+    /// Returns the start of the bucket of this timeframe containing `timestamp`,
+    /// buckets are aligned on multiples of the timeframe since the Unix epoch.
+    /// Saturates at `i64::MIN` for timestamps in the first representable bucket.
+    pub const fn align(&self, timestamp: i64) -> i64 {
+        timestamp.saturating_sub(timestamp.rem_euclid(self.as_millis()))
+    }
+}
+
+impl fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [(u64, &str); 4] = [(604_800, "w"), (86_400, "d"), (3_600, "h"), (60, "m")];
+        for (size, unit) in UNITS {
+            if self.seconds.is_multiple_of(size) {
+                return write!(f, "{}{unit}", self.seconds / size);
+            }
+        }
+        write!(f, "{}s", self.seconds)
+    }
+}
+
+impl FromStr for Timeframe {
+    type Err = TaUtilsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TaUtilsError::InvalidParameter(format!("Invalid timeframe '{s}'"));
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let count: u64 = s[..split].parse().map_err(|_| invalid())?;
+        match &s[split..] {
+            "s" => Timeframe::seconds(count),
+            "m" => Timeframe::minutes(count),
+            "h" => Timeframe::hours(count),
+            "d" => Timeframe::days(count),
+            "w" => Timeframe::weeks(count),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Serialize for Timeframe {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timeframe {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for Timeframe {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Timeframe".into()
+    }
+
+    fn json_schema(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "type": "string",
+            "pattern": "^[0-9]+[smhdw]$"
+        })
+    }
+}
+
 impl<T> Queue<T> {
     pub fn new(period: usize) -> TaUtilsResult<Self> {
         if period == 0 {
@@ -207,33 +696,291 @@ impl<T> Queue<T> {
             ));
         }
         Ok(Self {
-            queue: Vec::with_capacity(period),
+            buf: Vec::with_capacity(period),
+            head: 0,
             period,
         })
     }
 
+    /// Pushes a new element, returning the evicted oldest element if the queue was full.
     pub fn push(&mut self, value: T) -> Option<T> {
-        self.queue.push(value);
-        if self.queue.len() > self.period {
-            let removed = self.queue.remove(0);
+        if self.buf.len() < self.period {
+            self.buf.push(value);
+            None
+        } else {
+            let removed = std::mem::replace(&mut self.buf[self.head], value);
+            self.head += 1;
+            if self.head == self.period {
+                self.head = 0;
+            }
             Some(removed)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// Returns `true` once the queue holds `period` elements.
+    pub fn is_full(&self) -> bool {
+        self.buf.len() == self.period
+    }
+
+    /// Returns the element at `index`, where `0` is the oldest element.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.buf.len() {
+            return None;
+        }
+        let idx = self.head + index;
+        if idx >= self.buf.len() {
+            Some(&self.buf[idx - self.buf.len()])
+        } else {
+            Some(&self.buf[idx])
+        }
+    }
+
+    pub fn oldest(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn newest(&self) -> Option<&T> {
+        self.buf.len().checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    pub fn newest_mut(&mut self) -> Option<&mut T> {
+        let len = self.buf.len();
+        if len == 0 {
+            return None;
+        }
+        Some(&mut self.buf[(self.head + len - 1) % len])
+    }
+
+    /// Returns a view of the queue as it would be after pushing `value`, without pushing it.
+    pub fn peek_push<'a>(&'a self, value: &'a T) -> PeekQueue<'a, T> {
+        PeekQueue { queue: self, value }
+    }
+
+    /// Replaces the newest element, returning it, or pushes `value` if the queue is empty.
+    /// Used to update an element still forming, such as the current candle, without advancing.
+    pub fn replace_newest(&mut self, value: T) -> Option<T> {
+        match self.newest_mut() {
+            Some(newest) => Some(std::mem::replace(newest, value)),
+            None => {
+                self.buf.push(value);
+                None
+            }
+        }
+    }
+
+    /// Returns the contents as two slices which, concatenated, are in chronological order.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (wrapped, oldest) = self.buf.split_at(self.head);
+        (oldest, wrapped)
+    }
+
+    pub fn iter(&self) -> QueueIter<'_, T> {
+        let (a, b) = self.as_slices();
+        QueueIter {
+            inner: a.iter().chain(b.iter()),
+        }
+    }
+
+    /// Removes all the elements, keeping the period and the allocated buffer.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.head = 0;
+    }
+
+    /// Returns the elements in chronological order.
+    pub fn into_vec(mut self) -> Vec<T> {
+        self.buf.rotate_left(self.head);
+        self.buf
+    }
+
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Index<usize> for Queue<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match self.get(index) {
+            Some(value) => value,
+            None => panic!(
+                "index out of bounds: the len is {} but the index is {}",
+                self.len(),
+                index
+            ),
+        }
+    }
+}
+
+impl<'a, T> PeekQueue<'a, T> {
+    /// The element the push would evict, the oldest one if the queue is full.
+    pub fn evicted(&self) -> Option<&'a T> {
+        if self.queue.is_full() {
+            self.queue.oldest()
         } else {
             None
         }
     }
+
+    pub fn len(&self) -> usize {
+        (self.queue.len() + 1).min(self.queue.period)
+    }
+
+    /// Always `false`, the view holds at least the pushed element.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.queue.period
+    }
+
+    /// Returns the element at `index`, where `0` is the oldest element.
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        let skip = usize::from(self.queue.is_full());
+        match (index + skip).cmp(&self.queue.len()) {
+            Ordering::Less => self.queue.get(index + skip),
+            Ordering::Equal => Some(self.value),
+            Ordering::Greater => None,
+        }
+    }
+
+    pub fn oldest(&self) -> Option<&'a T> {
+        self.get(0)
+    }
+
+    /// The pushed element.
+    pub fn newest(&self) -> &'a T {
+        self.value
+    }
+
+    pub fn iter(&self) -> Chain<Skip<QueueIter<'a, T>>, Once<&'a T>> {
+        self.queue
+            .iter()
+            .skip(usize::from(self.queue.is_full()))
+            .chain(iter::once(self.value))
+    }
+}
+
+impl<T> Clone for PeekQueue<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for PeekQueue<'_, T> {}
+
+impl<'a, T> Iterator for QueueIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for QueueIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<T> ExactSizeIterator for QueueIter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Queue<T> {
+    type Item = &'a T;
+    type IntoIter = QueueIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> From<Queue<T>> for Vec<T> {
+    fn from(queue: Queue<T>) -> Self {
+        queue.into_vec()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Queue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Queue")
+            .field("queue", &self.iter().collect::<Vec<_>>())
+            .field("period", &self.period)
+            .finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Queue<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.period == other.period && self.iter().eq(other.iter())
+    }
+}
+
+/// Serialized form of a [`Queue`], elements are stored oldest first.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Queue")]
+struct QueueRepr<T> {
+    queue: Vec<T>,
+    period: usize,
 }
 
-impl<T> Deref for Queue<T> {
-    type Target = Vec<T>;
+impl<T: Serialize> Serialize for Queue<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Chronological<'a, T>(&'a Queue<T>);
 
-    fn deref(&self) -> &Self::Target {
-        &self.queue
+        impl<T: Serialize> Serialize for Chronological<'_, T> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(self.0.iter())
+            }
+        }
+
+        let mut state = serializer.serialize_struct("Queue", 2)?;
+        state.serialize_field("queue", &Chronological(self))?;
+        state.serialize_field("period", &self.period)?;
+        state.end()
     }
 }
 
-impl<T> DerefMut for Queue<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.queue
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Queue<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = QueueRepr::<T>::deserialize(deserializer)?;
+        let mut queue = Queue::new(repr.period).map_err(de::Error::custom)?;
+        for value in repr.queue {
+            queue.push(value);
+        }
+        Ok(queue)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl<T: schemars::JsonSchema> schemars::JsonSchema for Queue<T> {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        QueueRepr::<T>::schema_name()
+    }
+
+    fn schema_id() -> std::borrow::Cow<'static, str> {
+        QueueRepr::<T>::schema_id()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        QueueRepr::<T>::json_schema(generator)
     }
 }
 
@@ -244,6 +991,88 @@ impl<T> Period for Queue<T> {
 }
 impl<T> Reset for Queue<T> {
     fn reset(&mut self) {
-        self.queue = Vec::with_capacity(self.period);
+        self.clear();
+    }
+}
+
+snapshot_period!(impl [T: Clone + Serialize + DeserializeOwned] Queue<T>);
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::Snapshot;
+
+    #[test]
+    fn wraps_around_like_a_deque() {
+        for period in 1..6 {
+            let mut queue = Queue::new(period).unwrap();
+            let mut model = VecDeque::new();
+            for value in 0..3 * period + 2 {
+                let evicted = (model.len() == period).then(|| model.pop_front().unwrap());
+                model.push_back(value);
+                assert_eq!(queue.push(value), evicted);
+                assert_eq!(queue.to_vec(), Vec::from(model.clone()));
+                assert_eq!(queue.len(), model.len());
+                assert_eq!(queue.is_full(), model.len() == period);
+                assert_eq!(queue.oldest(), model.front());
+                assert_eq!(queue.newest(), model.back());
+                assert!(queue.iter().rev().eq(model.iter().rev()));
+                for index in 0..=period {
+                    assert_eq!(queue.get(index), model.get(index));
+                }
+            }
+            assert_eq!(queue.into_vec(), Vec::from(model));
+        }
+    }
+
+    #[test]
+    fn replaces_the_newest_element() {
+        let mut queue = Queue::new(3).unwrap();
+        assert_eq!(queue.replace_newest(1), None);
+        for value in 2..6 {
+            queue.push(value);
+        }
+        assert_eq!(queue.replace_newest(9), Some(5));
+        assert_eq!(queue.to_vec(), vec![3, 4, 9]);
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.newest(), None);
+    }
+
+    #[test]
+    fn peeks_at_a_push() {
+        let mut queue = Queue::new(3).unwrap();
+        queue.push(1);
+        let peek = queue.peek_push(&2);
+        assert_eq!(peek.evicted(), None);
+        assert_eq!(peek.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        for value in 2..5 {
+            queue.push(value);
+        }
+        let peek = queue.peek_push(&5);
+        assert_eq!(peek.evicted(), Some(&2));
+        assert_eq!(peek.len(), 3);
+        assert_eq!(peek.oldest(), Some(&3));
+        assert_eq!(peek.newest(), &5);
+        assert_eq!(peek.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(queue.to_vec(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn restores_snapshots_of_the_same_period() {
+        let mut queue = Queue::new(2).unwrap();
+        for value in 0..5 {
+            queue.push(value);
+        }
+        let mut other = Queue::new(2).unwrap();
+        other.restore(queue.snapshot()).unwrap();
+        assert_eq!(other, queue);
+        assert!(Queue::new(3).unwrap().restore(queue.snapshot()).is_err());
+        assert!(Queue::<i32>::new(0).is_err());
+
+        let json = serde_json::to_string(&queue).unwrap();
+        assert_eq!(serde_json::from_str::<Queue<i32>>(&json).unwrap(), queue);
     }
 }
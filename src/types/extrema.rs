@@ -0,0 +1,350 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::params::period_params;
+use crate::traits::snapshot_period;
+use crate::{Candle, Next, Peek, Period, Readiness, Reset, TaUtilsError, TaUtilsResult, Update};
+
+/// An extreme value of a window and how many bars ago it happened, `0` being the latest bar.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Extreme {
+    pub value: f64,
+    pub age: usize,
+}
+
+/// Highest high and lowest low of a [`RollingExtrema`] window.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Extrema {
+    pub highest: Extreme,
+    pub lowest: Extreme,
+}
+
+/// Rolling maximum and minimum over the last `period` bars.
+///
+/// Both extremes are tracked with monotonic deques of `(bar index, value)`
+/// pairs, so every update is amortized O(1). On ties the most recent bar wins,
+/// and `NaN` values are ignored.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollingExtrema {
+    period: usize,
+    /// Number of values pushed since the last reset.
+    index: usize,
+    /// Decreasing values, the front is the current maximum.
+    max: VecDeque<(usize, f64)>,
+    /// Increasing values, the front is the current minimum.
+    min: VecDeque<(usize, f64)>,
+    /// Entries the last push removed from `max` and `min`, to undo it on replace.
+    max_removed: Removed,
+    min_removed: Removed,
+}
+
+/// Entries removed from a deque by a push.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Removed {
+    /// Dominated by the pushed value, in the order they were popped from the back.
+    popped: Vec<(usize, f64)>,
+    /// Out of the window, in the order they were popped from the front.
+    expired: Vec<(usize, f64)>,
+}
+
+impl RollingExtrema {
+    pub fn new(period: usize) -> TaUtilsResult<Self> {
+        if period == 0 {
+            return Err(TaUtilsError::InvalidParameter(
+                "Period must be greater than 0".to_string(),
+            ));
+        }
+        Ok(Self {
+            period,
+            index: 0,
+            max: VecDeque::with_capacity(period),
+            min: VecDeque::with_capacity(period),
+            max_removed: Removed::default(),
+            min_removed: Removed::default(),
+        })
+    }
+
+    /// Pushes the high and low of a new bar into the window.
+    pub fn push(&mut self, high: f64, low: f64) {
+        let index = self.index;
+        self.index += 1;
+        let expired = self.index.checked_sub(self.period);
+        insert(
+            &mut self.max,
+            &mut self.max_removed,
+            (index, high),
+            expired,
+            |value| value <= high,
+        );
+        insert(
+            &mut self.min,
+            &mut self.min_removed,
+            (index, low),
+            expired,
+            |value| value >= low,
+        );
+    }
+
+    /// Replaces the high and low of the newest bar as if they had been pushed
+    /// instead, pushes them if the window is empty.
+    pub fn replace(&mut self, high: f64, low: f64) {
+        if let Some(index) = self.index.checked_sub(1) {
+            revert(&mut self.max, &mut self.max_removed, index);
+            revert(&mut self.min, &mut self.min_removed, index);
+            self.index = index;
+        }
+        self.push(high, low);
+    }
+
+    fn extreme(&self, front: Option<&(usize, f64)>) -> Option<Extreme> {
+        front.map(|&(index, value)| Extreme {
+            value,
+            age: self.index - 1 - index,
+        })
+    }
+
+    /// Highest value of the window, `None` if it holds no value.
+    pub fn highest(&self) -> Option<Extreme> {
+        self.extreme(self.max.front())
+    }
+
+    /// Lowest value of the window, `None` if it holds no value.
+    pub fn lowest(&self) -> Option<Extreme> {
+        self.extreme(self.min.front())
+    }
+
+    /// Both extremes of the window, missing ones have a `NaN` value and an age of `period`.
+    pub fn extrema(&self) -> Extrema {
+        self.complete(self.highest(), self.lowest())
+    }
+
+    /// The extrema the window would have after pushing a bar, without pushing it.
+    pub fn peek_push(&self, high: f64, low: f64) -> Extrema {
+        self.complete(
+            self.peek_extreme(&self.max, high, |value, current| value >= current),
+            self.peek_extreme(&self.min, low, |value, current| value <= current),
+        )
+    }
+
+    /// Extreme of `deque` once `value` is pushed, `wins` tells if it replaces the current one.
+    fn peek_extreme(
+        &self,
+        deque: &VecDeque<(usize, f64)>,
+        value: f64,
+        wins: fn(f64, f64) -> bool,
+    ) -> Option<Extreme> {
+        // Only the front can expire, so this looks at two entries at most.
+        let expired = (self.index + 1).saturating_sub(self.period);
+        let current = deque
+            .iter()
+            .find(|&&(index, _)| index >= expired)
+            .map(|&(index, value)| Extreme {
+                value,
+                age: self.index - index,
+            });
+        match current {
+            _ if value.is_nan() => current,
+            Some(current) if !wins(value, current.value) => Some(current),
+            _ => Some(Extreme { value, age: 0 }),
+        }
+    }
+
+    fn complete(&self, highest: Option<Extreme>, lowest: Option<Extreme>) -> Extrema {
+        let missing = Extreme {
+            value: f64::NAN,
+            age: self.period,
+        };
+        Extrema {
+            highest: highest.unwrap_or(missing),
+            lowest: lowest.unwrap_or(missing),
+        }
+    }
+
+    /// Returns `true` once `period` bars have been pushed.
+    pub fn is_full(&self) -> bool {
+        self.index >= self.period
+    }
+}
+
+impl Next<&dyn Candle> for RollingExtrema {
+    type Output = Extrema;
+
+    fn next(&mut self, input: &dyn Candle) -> TaUtilsResult<Self::Output> {
+        self.push(input.high(), input.low());
+        Ok(self.extrema())
+    }
+}
+
+impl Update<&dyn Candle> for RollingExtrema {
+    fn update(&mut self, input: &dyn Candle) -> TaUtilsResult<Self::Output> {
+        self.replace(input.high(), input.low());
+        Ok(self.extrema())
+    }
+}
+
+impl Peek<&dyn Candle> for RollingExtrema {
+    fn peek(&self, input: &dyn Candle) -> TaUtilsResult<Self::Output> {
+        Ok(self.peek_push(input.high(), input.low()))
+    }
+}
+
+// The 20 bars of the classic Donchian channel.
+period_params!(RollingExtrema, 20);
+
+impl Period for RollingExtrema {
+    fn period(&self) -> usize {
+        self.period
+    }
+}
+
+impl Readiness for RollingExtrema {
+    fn bars_seen(&self) -> usize {
+        self.index
+    }
+}
+
+snapshot_period!(RollingExtrema, 2);
+
+impl Reset for RollingExtrema {
+    fn reset(&mut self) {
+        self.index = 0;
+        self.max.clear();
+        self.min.clear();
+        self.max_removed = Removed::default();
+        self.min_removed = Removed::default();
+    }
+}
+
+/// Pushes `entry` at the back of `deque` unless its value is `NaN`, popping the
+/// entries it `dominates` and the ones older than `expired`, which are kept in `removed`.
+fn insert(
+    deque: &mut VecDeque<(usize, f64)>,
+    removed: &mut Removed,
+    entry: (usize, f64),
+    expired: Option<usize>,
+    dominates: impl Fn(f64) -> bool,
+) {
+    removed.popped.clear();
+    removed.expired.clear();
+    if !entry.1.is_nan() {
+        while let Some(&back) = deque.back().filter(|&&(_, value)| dominates(value)) {
+            deque.pop_back();
+            removed.popped.push(back);
+        }
+        deque.push_back(entry);
+    }
+    if let Some(expired) = expired {
+        while let Some(&front) = deque.front().filter(|&&(i, _)| i < expired) {
+            deque.pop_front();
+            removed.expired.push(front);
+        }
+    }
+}
+
+/// Undoes the [`insert`] of the entry at `index`, the newest one.
+fn revert(deque: &mut VecDeque<(usize, f64)>, removed: &mut Removed, index: usize) {
+    for &entry in removed.expired.iter().rev() {
+        deque.push_front(entry);
+    }
+    if deque.back().is_some_and(|&(i, _)| i == index) {
+        deque.pop_back();
+    }
+    deque.extend(removed.popped.drain(..).rev());
+    removed.expired.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Extreme of the last `period` values, the latest one winning ties.
+    fn brute_force(values: &[f64], period: usize, wins: fn(f64, f64) -> bool) -> Option<Extreme> {
+        let window = &values[values.len().saturating_sub(period)..];
+        let mut best: Option<Extreme> = None;
+        for (i, &value) in window.iter().enumerate() {
+            if value.is_nan() {
+                continue;
+            }
+            if best.is_none_or(|best| value == best.value || wins(value, best.value)) {
+                best = Some(Extreme {
+                    value,
+                    age: window.len() - 1 - i,
+                });
+            }
+        }
+        best
+    }
+
+    fn check(extrema: &RollingExtrema, highs: &[f64], lows: &[f64]) {
+        let period = extrema.period();
+        assert_eq!(extrema.highest(), brute_force(highs, period, |a, b| a > b));
+        assert_eq!(extrema.lowest(), brute_force(lows, period, |a, b| a < b));
+    }
+
+    #[test]
+    fn matches_a_brute_force_window() {
+        // Few distinct values so ties are frequent, every 7th one is `NaN`.
+        let mut seed = 7_u64;
+        let mut sample = move || {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            let value = (seed >> 33) % 8;
+            if value == 7 { f64::NAN } else { value as f64 }
+        };
+        for period in 1..5 {
+            let mut extrema = RollingExtrema::new(period).unwrap();
+            let (mut highs, mut lows) = (Vec::new(), Vec::new());
+            for step in 0..200 {
+                let (high, low) = (sample(), sample());
+                let peeked = extrema.peek_push(high, low);
+                if step % 3 == 2 {
+                    extrema.replace(high, low);
+                    highs.pop();
+                    lows.pop();
+                } else {
+                    let mut pushed = extrema.clone();
+                    pushed.push(high, low);
+                    assert_eq!(format!("{peeked:?}"), format!("{:?}", pushed.extrema()));
+                    extrema.push(high, low);
+                }
+                highs.push(high);
+                lows.push(low);
+                check(&extrema, &highs, &lows);
+            }
+        }
+    }
+
+    #[test]
+    fn replaces_repeatedly() {
+        let mut extrema = RollingExtrema::new(3).unwrap();
+        extrema.replace(5.0, 1.0);
+        extrema.push(4.0, 2.0);
+        extrema.push(3.0, 3.0);
+        extrema.push(2.0, 4.0);
+        for (high, low) in [(9.0, 0.0), (1.0, 9.0), (f64::NAN, f64::NAN), (4.0, 2.0)] {
+            extrema.replace(high, low);
+            check(&extrema, &[5.0, 4.0, 3.0, high], &[1.0, 2.0, 3.0, low]);
+        }
+        assert_eq!(extrema.highest(), Some(Extreme { value: 4.0, age: 0 }));
+        assert_eq!(extrema.lowest(), Some(Extreme { value: 2.0, age: 0 }));
+    }
+
+    #[test]
+    fn fills_missing_extremes() {
+        let mut extrema = RollingExtrema::new(2).unwrap();
+        extrema.push(f64::NAN, f64::NAN);
+        let missing = extrema.extrema();
+        assert!(missing.highest.value.is_nan() && missing.lowest.value.is_nan());
+        assert_eq!((missing.highest.age, missing.lowest.age), (2, 2));
+        assert!(!extrema.is_full());
+        extrema.push(1.0, 1.0);
+        assert!(extrema.is_full());
+        extrema.reset();
+        assert_eq!(extrema.bars_seen(), 0);
+        assert_eq!(extrema.highest(), None);
+    }
+}
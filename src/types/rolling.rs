@@ -0,0 +1,723 @@
+use serde::{Deserialize, Serialize};
+
+use crate::params::period_params;
+use crate::traits::snapshot_period;
+use crate::{Next, Peek, Period, Queue, Readiness, Reset, TaUtilsResult, Update};
+
+/// If a second moment shrinks by more than this factor in a single update most of
+/// its significant digits were lost to cancellation, so it gets recomputed.
+const CANCELLATION_RATIO: f64 = 1e-6;
+
+/// Neumaier compensated summation, keeps the rounding error of long running sums bounded.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct KahanSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl KahanSum {
+    fn add(&mut self, value: f64) {
+        let total = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - total) + value;
+        } else {
+            self.compensation += (value - total) + self.sum;
+        }
+        self.sum = total;
+    }
+
+    fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+/// Sum, mean and variance of a set of values, the statistics of a [`RollingStats`] window.
+///
+/// Non finite values are excluded from the moments, but make every statistic `NaN`.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Moments {
+    sum: KahanSum,
+    mean: f64,
+    m2: f64,
+    count: usize,
+    non_finite: usize,
+}
+
+impl Moments {
+    /// Computes the moments from scratch, with a second pass for the variance.
+    fn from_values<'a>(values: impl Iterator<Item = &'a f64> + Clone) -> Self {
+        let mut moments = Self::default();
+        for &value in values.clone() {
+            if value.is_finite() {
+                moments.sum.add(value);
+                moments.count += 1;
+            } else {
+                moments.non_finite += 1;
+            }
+        }
+        if moments.count > 0 {
+            moments.mean = moments.sum.value() / moments.count as f64;
+        }
+        moments.m2 = values
+            .filter(|value| value.is_finite())
+            .map(|value| (value - moments.mean).powi(2))
+            .sum();
+        moments
+    }
+
+    fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            self.non_finite += 1;
+            return;
+        }
+        self.count += 1;
+        self.sum.add(value);
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn remove(&mut self, value: f64) {
+        if !value.is_finite() {
+            self.non_finite -= 1;
+            return;
+        }
+        self.count -= 1;
+        self.sum.add(-value);
+        if self.count == 0 {
+            self.sum = KahanSum::default();
+            self.mean = 0.0;
+            self.m2 = 0.0;
+            return;
+        }
+        let delta = value - self.mean;
+        self.mean -= delta / self.count as f64;
+        self.m2 = (self.m2 - delta * (value - self.mean)).max(0.0);
+    }
+
+    fn guard(&self, value: f64) -> f64 {
+        if self.non_finite > 0 { f64::NAN } else { value }
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.guard(self.sum.value())
+    }
+
+    /// Mean of the values, `NaN` if there are none.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return f64::NAN;
+        }
+        self.guard(self.mean)
+    }
+
+    /// Population variance of the values, `NaN` if there are none.
+    pub fn variance(&self) -> f64 {
+        if self.count == 0 {
+            return f64::NAN;
+        }
+        self.guard(self.m2 / self.count as f64)
+    }
+
+    /// Sample (Bessel corrected) variance of the values, `NaN` with less than two values.
+    pub fn sample_variance(&self) -> f64 {
+        if self.count < 2 {
+            return f64::NAN;
+        }
+        self.guard(self.m2 / (self.count - 1) as f64)
+    }
+
+    pub fn stddev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    pub fn sample_stddev(&self) -> f64 {
+        self.sample_variance().sqrt()
+    }
+
+    /// Number of standard deviations `value` is away from the mean.
+    /// Returns `0.0` when the values have no dispersion.
+    pub fn z_score(&self, value: f64) -> f64 {
+        let stddev = self.stddev();
+        if stddev == 0.0 {
+            return 0.0;
+        }
+        (value - self.mean()) / stddev
+    }
+}
+
+/// Incrementally updated sum, mean and variance over the last `period` values.
+///
+/// Moments are maintained with Welford's algorithm, adding the new value and
+/// removing the evicted one on every push, so each update is O(1). Once per full
+/// rotation of the window they are recomputed from scratch so rounding drift
+/// cannot accumulate over long streams, which keeps the amortized cost O(1).
+/// The same happens right away when the variance collapses, e.g. after a spike
+/// leaves the window.
+/// Non finite values are kept in the window but excluded from the moments,
+/// while any of them is inside the window every statistic is `NaN`.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollingStats {
+    window: Queue<f64>,
+    moments: Moments,
+    since_refresh: usize,
+}
+
+impl RollingStats {
+    pub fn new(period: usize) -> TaUtilsResult<Self> {
+        Ok(Self {
+            window: Queue::new(period)?,
+            moments: Moments::default(),
+            since_refresh: 0,
+        })
+    }
+
+    /// Pushes a new value into the window, returning the evicted value if the window was full.
+    pub fn push(&mut self, value: f64) -> Option<f64> {
+        let evicted = self.window.push(value);
+        self.slide(evicted, value);
+        evicted
+    }
+
+    /// Replaces the newest value of the window as if `value` had been pushed instead,
+    /// returning the replaced value. Pushes `value` if the window is empty.
+    pub fn replace(&mut self, value: f64) -> Option<f64> {
+        let replaced = self.window.replace_newest(value);
+        self.slide(replaced, value);
+        replaced
+    }
+
+    /// Updates the moments for `value` taking the place of `old` in the window.
+    fn slide(&mut self, old: Option<f64>, value: f64) {
+        let previous = self.moments;
+        if let Some(old) = old {
+            self.moments.remove(old);
+        }
+        self.moments.add(value);
+        if old.is_some() && self.needs_refresh(&previous) {
+            self.refresh();
+        }
+    }
+
+    /// Statistics the window would have after pushing `value`, without pushing it.
+    pub fn peek_push(&self, value: f64) -> Moments {
+        let window = self.window.peek_push(&value);
+        let mut moments = self.moments;
+        if let Some(&old) = window.evicted() {
+            moments.remove(old);
+        }
+        moments.add(value);
+        let refresh = self.since_refresh + 1 >= self.window.period()
+            || moments.m2 < self.moments.m2 * CANCELLATION_RATIO;
+        if window.evicted().is_some() && refresh {
+            moments = Moments::from_values(window.iter());
+        }
+        moments
+    }
+
+    /// Counts an update replacing a value and tells if the moments must be recomputed.
+    fn needs_refresh(&mut self, previous: &Moments) -> bool {
+        self.since_refresh += 1;
+        self.since_refresh >= self.window.period()
+            || self.moments.m2 < previous.m2 * CANCELLATION_RATIO
+    }
+
+    /// Recomputes every moment from the values in the window.
+    fn refresh(&mut self) {
+        self.moments = Moments::from_values(self.window.iter());
+        self.since_refresh = 0;
+    }
+
+    /// The values currently in the window, oldest first.
+    pub fn window(&self) -> &Queue<f64> {
+        &self.window
+    }
+
+    /// The statistics of the window.
+    pub fn moments(&self) -> &Moments {
+        &self.moments
+    }
+
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.window.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.window.is_full()
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.moments.sum()
+    }
+
+    /// Mean of the window, `NaN` if it is empty.
+    pub fn mean(&self) -> f64 {
+        self.moments.mean()
+    }
+
+    /// Population variance of the window, `NaN` if it is empty.
+    pub fn variance(&self) -> f64 {
+        self.moments.variance()
+    }
+
+    /// Sample (Bessel corrected) variance of the window, `NaN` with less than two values.
+    pub fn sample_variance(&self) -> f64 {
+        self.moments.sample_variance()
+    }
+
+    pub fn stddev(&self) -> f64 {
+        self.moments.stddev()
+    }
+
+    pub fn sample_stddev(&self) -> f64 {
+        self.moments.sample_stddev()
+    }
+
+    /// Number of standard deviations `value` is away from the window mean.
+    /// Returns `0.0` when the window has no dispersion.
+    pub fn z_score(&self, value: f64) -> f64 {
+        self.moments.z_score(value)
+    }
+}
+
+impl Period for RollingStats {
+    fn period(&self) -> usize {
+        self.window.period()
+    }
+}
+
+impl Readiness for RollingStats {
+    fn bars_seen(&self) -> usize {
+        self.window.len()
+    }
+}
+
+snapshot_period!(RollingStats, 2);
+
+impl Reset for RollingStats {
+    fn reset(&mut self) {
+        self.window.reset();
+        self.moments = Moments::default();
+        self.since_refresh = 0;
+    }
+}
+
+macro_rules! rolling_stat {
+    ($(#[$meta:meta])* $name:ident, $default:literal, |$stats:ident, $input:ident| $output:expr) => {
+        $(#[$meta])*
+        #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+        pub struct $name {
+            stats: RollingStats,
+        }
+
+        impl $name {
+            pub fn new(period: usize) -> TaUtilsResult<Self> {
+                Ok(Self {
+                    stats: RollingStats::new(period)?,
+                })
+            }
+
+            pub fn stats(&self) -> &RollingStats {
+                &self.stats
+            }
+        }
+
+        impl Next<f64> for $name {
+            type Output = f64;
+
+            fn next(&mut self, $input: f64) -> TaUtilsResult<Self::Output> {
+                self.stats.push($input);
+                let $stats = self.stats.moments();
+                Ok($output)
+            }
+
+            fn next_slice(&mut self, inputs: &[f64], outputs: &mut [f64]) -> TaUtilsResult<()> {
+                for (&$input, output) in inputs.iter().zip(outputs) {
+                    self.stats.push($input);
+                    let $stats = self.stats.moments();
+                    *output = $output;
+                }
+                Ok(())
+            }
+        }
+
+        impl Update<f64> for $name {
+            fn update(&mut self, $input: f64) -> TaUtilsResult<Self::Output> {
+                self.stats.replace($input);
+                let $stats = self.stats.moments();
+                Ok($output)
+            }
+        }
+
+        impl Peek<f64> for $name {
+            fn peek(&self, $input: f64) -> TaUtilsResult<Self::Output> {
+                let $stats = &self.stats.peek_push($input);
+                Ok($output)
+            }
+        }
+
+        period_params!($name, $default);
+
+        impl Period for $name {
+            fn period(&self) -> usize {
+                self.stats.period()
+            }
+        }
+
+        impl Readiness for $name {
+            fn bars_seen(&self) -> usize {
+                self.stats.bars_seen()
+            }
+        }
+
+        snapshot_period!($name, 2);
+
+        impl Reset for $name {
+            fn reset(&mut self) {
+                self.stats.reset();
+            }
+        }
+    };
+}
+
+rolling_stat!(
+    /// Rolling sum over the last `period` values.
+    RollingSum,
+    20,
+    |stats, _input| stats.sum()
+);
+
+rolling_stat!(
+    /// Rolling arithmetic mean over the last `period` values.
+    RollingMean,
+    20,
+    |stats, _input| stats.mean()
+);
+
+rolling_stat!(
+    /// Rolling population variance over the last `period` values.
+    RollingVariance,
+    20,
+    |stats, _input| stats.variance()
+);
+
+rolling_stat!(
+    /// Rolling population standard deviation over the last `period` values.
+    RollingStdDev,
+    20,
+    |stats, _input| stats.stddev()
+);
+
+rolling_stat!(
+    /// Z-score of each new value against the window it was pushed into.
+    RollingZScore,
+    20,
+    |stats, input| stats.z_score(input)
+);
+
+/// Welford co-moments of a paired series.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct CoMoments {
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    comoment: f64,
+    count: usize,
+    non_finite: usize,
+}
+
+impl CoMoments {
+    fn from_pairs<'a>(pairs: impl Iterator<Item = &'a (f64, f64)>) -> Self {
+        let mut moments = Self::default();
+        for &(x, y) in pairs {
+            moments.add(x, y);
+        }
+        moments
+    }
+
+    fn add(&mut self, x: f64, y: f64) {
+        if !x.is_finite() || !y.is_finite() {
+            self.non_finite += 1;
+            return;
+        }
+        self.count += 1;
+        let n = self.count as f64;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / n;
+        self.mean_y += dy / n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.comoment += dx * (y - self.mean_y);
+    }
+
+    fn remove(&mut self, x: f64, y: f64) {
+        if !x.is_finite() || !y.is_finite() {
+            self.non_finite -= 1;
+            return;
+        }
+        if self.count == 1 {
+            *self = Self {
+                non_finite: self.non_finite,
+                ..Self::default()
+            };
+            return;
+        }
+        self.count -= 1;
+        let n = self.count as f64;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x -= dx / n;
+        self.mean_y -= dy / n;
+        self.m2_x = (self.m2_x - dx * (x - self.mean_x)).max(0.0);
+        self.m2_y = (self.m2_y - dy * (y - self.mean_y)).max(0.0);
+        self.comoment -= dx * (y - self.mean_y);
+    }
+
+    fn guard(&self, value: f64) -> f64 {
+        if self.non_finite > 0 { f64::NAN } else { value }
+    }
+
+    fn covariance(&self) -> f64 {
+        if self.count == 0 {
+            return f64::NAN;
+        }
+        self.guard(self.comoment / self.count as f64)
+    }
+
+    fn sample_covariance(&self) -> f64 {
+        if self.count < 2 {
+            return f64::NAN;
+        }
+        self.guard(self.comoment / (self.count - 1) as f64)
+    }
+
+    fn correlation(&self) -> f64 {
+        let denominator = (self.m2_x * self.m2_y).sqrt();
+        if denominator == 0.0 {
+            return f64::NAN;
+        }
+        self.guard((self.comoment / denominator).clamp(-1.0, 1.0))
+    }
+}
+
+/// Incrementally updated covariance and correlation between two series over the last `period` pairs.
+///
+/// Updates follow the same scheme as [`RollingStats`], pairs where either
+/// value is non finite make every statistic `NaN` while they are in the window.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollingCovariance {
+    window: Queue<(f64, f64)>,
+    moments: CoMoments,
+    since_refresh: usize,
+}
+
+impl RollingCovariance {
+    pub fn new(period: usize) -> TaUtilsResult<Self> {
+        Ok(Self {
+            window: Queue::new(period)?,
+            moments: CoMoments::default(),
+            since_refresh: 0,
+        })
+    }
+
+    /// Pushes a new pair into the window, returning the evicted pair if the window was full.
+    pub fn push(&mut self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let evicted = self.window.push((x, y));
+        self.slide(evicted, x, y);
+        evicted
+    }
+
+    /// Replaces the newest pair of the window as if `(x, y)` had been pushed instead,
+    /// returning the replaced pair. Pushes the pair if the window is empty.
+    pub fn replace(&mut self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let replaced = self.window.replace_newest((x, y));
+        self.slide(replaced, x, y);
+        replaced
+    }
+
+    /// Updates the co-moments for `(x, y)` taking the place of `old` in the window.
+    fn slide(&mut self, old: Option<(f64, f64)>, x: f64, y: f64) {
+        let previous = self.moments;
+        if let Some((old_x, old_y)) = old {
+            self.moments.remove(old_x, old_y);
+        }
+        self.moments.add(x, y);
+        if old.is_some() && self.needs_refresh(&previous) {
+            self.refresh();
+        }
+    }
+
+    /// Counts an update replacing a pair and tells if the co-moments must be recomputed.
+    fn needs_refresh(&mut self, previous: &CoMoments) -> bool {
+        self.since_refresh += 1;
+        self.since_refresh >= self.window.period()
+            || self.moments.m2_x < previous.m2_x * CANCELLATION_RATIO
+            || self.moments.m2_y < previous.m2_y * CANCELLATION_RATIO
+    }
+
+    /// Co-moments the window would have after pushing `(x, y)`, without pushing it.
+    fn peek_moments(&self, x: f64, y: f64) -> CoMoments {
+        let pair = (x, y);
+        let window = self.window.peek_push(&pair);
+        let mut moments = self.moments;
+        if let Some(&(old_x, old_y)) = window.evicted() {
+            moments.remove(old_x, old_y);
+        }
+        moments.add(x, y);
+        let refresh = self.since_refresh + 1 >= self.window.period()
+            || moments.m2_x < self.moments.m2_x * CANCELLATION_RATIO
+            || moments.m2_y < self.moments.m2_y * CANCELLATION_RATIO;
+        if window.evicted().is_some() && refresh {
+            moments = CoMoments::from_pairs(window.iter());
+        }
+        moments
+    }
+
+    /// Recomputes the co-moments from the pairs in the window.
+    fn refresh(&mut self) {
+        self.moments = CoMoments::from_pairs(self.window.iter());
+        self.since_refresh = 0;
+    }
+
+    /// The pairs currently in the window, oldest first.
+    pub fn window(&self) -> &Queue<(f64, f64)> {
+        &self.window
+    }
+
+    /// Population covariance of the window, `NaN` if it is empty.
+    pub fn covariance(&self) -> f64 {
+        self.moments.covariance()
+    }
+
+    /// Sample (Bessel corrected) covariance of the window, `NaN` with less than two pairs.
+    pub fn sample_covariance(&self) -> f64 {
+        self.moments.sample_covariance()
+    }
+
+    /// Pearson correlation of the window, `NaN` if either series has no dispersion.
+    pub fn correlation(&self) -> f64 {
+        self.moments.correlation()
+    }
+}
+
+impl Next<(f64, f64)> for RollingCovariance {
+    type Output = f64;
+
+    fn next(&mut self, (x, y): (f64, f64)) -> TaUtilsResult<Self::Output> {
+        self.push(x, y);
+        Ok(self.covariance())
+    }
+}
+
+impl Peek<(f64, f64)> for RollingCovariance {
+    fn peek(&self, (x, y): (f64, f64)) -> TaUtilsResult<Self::Output> {
+        Ok(self.peek_moments(x, y).covariance())
+    }
+}
+
+impl Update<(f64, f64)> for RollingCovariance {
+    fn update(&mut self, (x, y): (f64, f64)) -> TaUtilsResult<Self::Output> {
+        self.replace(x, y);
+        Ok(self.covariance())
+    }
+}
+
+// Rolling correlations and betas are usually taken over about a month of daily bars.
+period_params!(RollingCovariance, 30);
+
+impl Period for RollingCovariance {
+    fn period(&self) -> usize {
+        self.window.period()
+    }
+}
+
+impl Readiness for RollingCovariance {
+    fn bars_seen(&self) -> usize {
+        self.window.len()
+    }
+}
+
+snapshot_period!(RollingCovariance);
+
+impl Reset for RollingCovariance {
+    fn reset(&mut self) {
+        self.window.reset();
+        self.moments = CoMoments::default();
+        self.since_refresh = 0;
+    }
+}
+
+/// Rolling Pearson correlation between two series over the last `period` pairs.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollingCorrelation {
+    covariance: RollingCovariance,
+}
+
+impl RollingCorrelation {
+    pub fn new(period: usize) -> TaUtilsResult<Self> {
+        Ok(Self {
+            covariance: RollingCovariance::new(period)?,
+        })
+    }
+
+    pub fn covariance(&self) -> &RollingCovariance {
+        &self.covariance
+    }
+}
+
+impl Next<(f64, f64)> for RollingCorrelation {
+    type Output = f64;
+
+    fn next(&mut self, (x, y): (f64, f64)) -> TaUtilsResult<Self::Output> {
+        self.covariance.push(x, y);
+        Ok(self.covariance.correlation())
+    }
+}
+
+impl Peek<(f64, f64)> for RollingCorrelation {
+    fn peek(&self, (x, y): (f64, f64)) -> TaUtilsResult<Self::Output> {
+        Ok(self.covariance.peek_moments(x, y).correlation())
+    }
+}
+
+impl Update<(f64, f64)> for RollingCorrelation {
+    fn update(&mut self, (x, y): (f64, f64)) -> TaUtilsResult<Self::Output> {
+        self.covariance.replace(x, y);
+        Ok(self.covariance.correlation())
+    }
+}
+
+period_params!(RollingCorrelation, 30);
+
+impl Period for RollingCorrelation {
+    fn period(&self) -> usize {
+        self.covariance.period()
+    }
+}
+
+impl Readiness for RollingCorrelation {
+    fn bars_seen(&self) -> usize {
+        self.covariance.bars_seen()
+    }
+}
+
+snapshot_period!(RollingCorrelation);
+
+impl Reset for RollingCorrelation {
+    fn reset(&mut self) {
+        self.covariance.reset();
+    }
+}
@@ -0,0 +1,114 @@
+use core::fmt;
+
+use crate::params::{ParamDescriptor, ParamMap};
+use crate::{IndicatorTrait, Next, OutputShape, Period, Readiness, Reset, TaUtilsResult, Update};
+
+/// Wraps an indicator so its outputs are `None` until it is ready, instead of the
+/// values it computes from an incomplete history. See [`Readiness::is_ready`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warmup<N> {
+    inner: N,
+}
+
+impl<N> Warmup<N> {
+    pub fn new(inner: N) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut N {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> N {
+        self.inner
+    }
+}
+
+impl<T, N: Next<T> + Readiness> Next<T> for Warmup<N> {
+    type Output = Option<N::Output>;
+
+    fn next(&mut self, input: T) -> TaUtilsResult<Self::Output> {
+        let output = self.inner.next(input)?;
+        Ok(self.inner.is_ready().then_some(output))
+    }
+}
+
+impl<T, N: Update<T> + Readiness> Update<T> for Warmup<N> {
+    fn update(&mut self, input: T) -> TaUtilsResult<Self::Output> {
+        let output = self.inner.update(input)?;
+        Ok(self.inner.is_ready().then_some(output))
+    }
+}
+
+impl<N: Period> Period for Warmup<N> {
+    fn period(&self) -> usize {
+        self.inner.period()
+    }
+}
+
+impl<N: Reset> Reset for Warmup<N> {
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for Warmup<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<N: Readiness> Readiness for Warmup<N> {
+    fn bars_seen(&self) -> usize {
+        self.inner.bars_seen()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+}
+
+impl<N: IndicatorTrait> IndicatorTrait for Warmup<N> {
+    fn output_shape(&self) -> OutputShape {
+        self.inner.output_shape()
+    }
+
+    fn param_descriptors(&self) -> Vec<ParamDescriptor> {
+        self.inner.param_descriptors()
+    }
+
+    fn param_values(&self) -> ParamMap {
+        self.inner.param_values()
+    }
+
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn readiness(&self) -> Option<&dyn Readiness> {
+        self.inner.readiness()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::{Doji, PatternConfig};
+
+    #[test]
+    fn forwards_parameters() {
+        let doji = Doji::new(PatternConfig::default()).unwrap();
+        let warmup = Warmup::new(doji.clone());
+        assert_eq!(warmup.param_descriptors(), doji.param_descriptors());
+        assert_eq!(warmup.param_values(), doji.param_values());
+        assert!(!warmup.param_values().is_empty());
+    }
+}